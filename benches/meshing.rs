@@ -0,0 +1,133 @@
+//! Compares naive (one quad per face) and greedy chunk meshing on sample
+//! terrain: rolling hills with a lake, and a superflat world.
+//!
+//! Usage: `cargo bench --bench meshing [-- iterations]`, at least 1 iteration.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use metalcraft::block::{BlockId, BlockRegistry};
+use metalcraft::math::IVec3;
+use metalcraft::mesh::Mesh;
+use metalcraft::mesher::{ChunkNeighbors, Mesher, MeshingMode};
+use metalcraft::world::{light_chunk, Chunk, ChunkPos, CHUNK_SIZE};
+
+/// Chunks per side of the sample area; only the inner chunks are meshed so
+/// every meshed chunk has all four neighbours.
+const AREA: i32 = 6;
+
+struct Terrain {
+    name: &'static str,
+    chunks: HashMap<ChunkPos, Chunk>,
+}
+
+impl Terrain {
+    fn generate(name: &'static str, registry: &BlockRegistry, height: impl Fn(i32, i32) -> i32) -> Self {
+        let stone = registry.expect_id("stone");
+        let dirt = registry.expect_id("dirt");
+        let grass = registry.expect_id("grass");
+        let sand = registry.expect_id("sand");
+        let water = registry.expect_id("water");
+        let sea_level = 62;
+
+        let mut chunks = HashMap::new();
+        for cx in 0..AREA {
+            for cz in 0..AREA {
+                let pos = ChunkPos::new(cx, cz);
+                let mut chunk = Chunk::new(pos);
+                let origin = pos.origin();
+                for x in 0..CHUNK_SIZE as i32 {
+                    for z in 0..CHUNK_SIZE as i32 {
+                        let top = height(origin.x + x, origin.z + z);
+                        let (surface, filler): (BlockId, BlockId) =
+                            if top < sea_level { (sand, sand) } else { (grass, dirt) };
+                        chunk.fill_box(IVec3::new(x, 0, z), IVec3::new(x, top - 4, z), stone);
+                        chunk.fill_box(IVec3::new(x, top - 3, z), IVec3::new(x, top - 1, z), filler);
+                        chunk.set(x as usize, top as usize, z as usize, surface);
+                        if top < sea_level {
+                            chunk.fill_box(IVec3::new(x, top + 1, z), IVec3::new(x, sea_level, z), water);
+                        }
+                    }
+                }
+                light_chunk(&mut chunk, registry);
+                chunks.insert(pos, chunk);
+            }
+        }
+        Terrain { name, chunks }
+    }
+
+    fn neighbors(&self, pos: ChunkPos) -> ChunkNeighbors<'_> {
+        ChunkNeighbors::from_fn(pos, |pos| self.chunks.get(&pos))
+    }
+
+    fn inner_chunks(&self) -> impl Iterator<Item = ChunkPos> {
+        (1..AREA - 1).flat_map(|cx| (1..AREA - 1).map(move |cz| ChunkPos::new(cx, cz)))
+    }
+
+    fn mesh_all(&self, mesher: &Mesher, registry: &BlockRegistry) -> Vec<Mesh> {
+        self.inner_chunks()
+            .map(|pos| mesher.mesh(&self.chunks[&pos], &self.neighbors(pos), registry))
+            .collect()
+    }
+}
+
+struct Measurement {
+    vertices: usize,
+    triangles: usize,
+    time: Duration,
+}
+
+fn run(terrain: &Terrain, mode: MeshingMode, registry: &BlockRegistry, iterations: u32) -> Measurement {
+    let mesher = Mesher::new(mode);
+    let meshes = terrain.mesh_all(&mesher, registry);
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(terrain.mesh_all(&mesher, black_box(registry)));
+    }
+    let chunks = terrain.inner_chunks().count() as u32;
+
+    Measurement {
+        vertices: meshes.iter().map(|mesh| mesh.vertices.len()).sum(),
+        triangles: meshes.iter().map(Mesh::triangle_count).sum(),
+        time: start.elapsed() / (iterations * chunks),
+    }
+}
+
+fn main() {
+    let iterations = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse::<u32>().ok())
+        .unwrap_or(10)
+        .max(1);
+    let registry = BlockRegistry::builtin();
+
+    let terrains = [
+        Terrain::generate("hills", &registry, |x, z| {
+            let (x, z) = (x as f32, z as f32);
+            (64.0 + 6.0 * (x * 0.11).sin() + 5.0 * (z * 0.07).cos() + 3.0 * ((x + z) * 0.23).sin()) as i32
+        }),
+        Terrain::generate("flat", &registry, |_, _| 64),
+    ];
+
+    println!(
+        "{:<8} {:<8} {:>10} {:>10} {:>12}",
+        "terrain", "mode", "vertices", "triangles", "time/chunk"
+    );
+    for terrain in &terrains {
+        let naive = run(terrain, MeshingMode::Naive, &registry, iterations);
+        let greedy = run(terrain, MeshingMode::Greedy, &registry, iterations);
+        for (mode, result) in [("naive", &naive), ("greedy", &greedy)] {
+            println!(
+                "{:<8} {:<8} {:>10} {:>10} {:>12.2?}",
+                terrain.name, mode, result.vertices, result.triangles, result.time
+            );
+        }
+        println!(
+            "{:<8} greedy emits {:.1}% of the naive vertices\n",
+            terrain.name,
+            100.0 * greedy.vertices as f64 / naive.vertices as f64
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use winit::{
+    dpi::{LogicalSize, PhysicalSize},
+    event::{ElementState, Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::{CursorGrabMode, Window, WindowBuilder},
+};
+
+use crate::engine::Engine;
+use crate::input::{Action, Bindings, InputState};
+use crate::platform::macos;
+use crate::renderer::{MetalRenderer, Renderer, RendererConfig};
+
+/// User bindings file, looked up in the working directory.
+const BINDINGS_FILE: &str = "bindings.toml";
+
+/// The application shell: owns the window, the renderer, the input state
+/// and the `Engine`.
+///
+/// Lifecycle is `App::new` (init) followed by `update` and `render` once per
+/// frame. `App::run` drives both from a winit event loop; embedders that own
+/// their own loop can call the phases directly.
+pub struct App {
+    window: Window,
+    renderer: MetalRenderer,
+    engine: Engine,
+    input: InputState,
+    /// Whether the cursor is hidden and locked for mouse look.
+    cursor_grabbed: bool,
+
+    /// Frames since `last_fps_update`, and the rate measured over the
+    /// second before it.
+    frame_count: u32,
+    last_fps_update: Instant,
+    fps: f32,
+    last_update: Instant,
+}
+
+impl App {
+    pub fn new(event_loop: &EventLoop<()>) -> Self {
+        Self::with_config(event_loop, RendererConfig::default())
+    }
+
+    pub fn with_config(event_loop: &EventLoop<()>, config: RendererConfig) -> Self {
+        // Create a winit window
+        let builder = WindowBuilder::new()
+            .with_title("Metal Triangle Example")
+            .with_inner_size(LogicalSize::new(800.0, 600.0));
+        let window = macos::configure_window(builder)
+            .build(event_loop)
+            .unwrap();
+
+        let mut renderer = MetalRenderer::with_config(config);
+        macos::attach_layer(&window, renderer.layer());
+        renderer.set_scale_factor(window.scale_factor());
+
+        let engine = Engine::new(&mut renderer);
+        let bindings = if Path::new(BINDINGS_FILE).exists() {
+            Bindings::load(BINDINGS_FILE).unwrap_or_else(|e| {
+                eprintln!("{}: {}; using the default bindings", BINDINGS_FILE, e);
+                Bindings::builtin()
+            })
+        } else {
+            Bindings::builtin()
+        };
+
+        let now = Instant::now();
+        let mut app = App {
+            window,
+            renderer,
+            engine,
+            input: InputState::new(bindings),
+            cursor_grabbed: false,
+            frame_count: 0,
+            last_fps_update: now,
+            fps: 0.0,
+            last_update: now,
+        };
+        app.resize(app.window.inner_size());
+        app
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn engine(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Frames per second, measured over about the last second; 0 until the
+    /// first second has passed.
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Hides the cursor and locks it to the window so mouse motion turns the
+    /// view. Falls back to confining it where locking is unsupported.
+    pub fn grab_cursor(&mut self) {
+        let grabbed = self
+            .window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined));
+        match grabbed {
+            Ok(()) => {
+                self.window.set_cursor_visible(false);
+                self.cursor_grabbed = true;
+            }
+            Err(e) => eprintln!("cannot grab the cursor: {}", e),
+        }
+    }
+
+    /// Gives the cursor back; mouse motion no longer turns the view.
+    pub fn release_cursor(&mut self) {
+        if let Err(e) = self.window.set_cursor_grab(CursorGrabMode::None) {
+            eprintln!("cannot release the cursor: {}", e);
+        }
+        self.window.set_cursor_visible(true);
+        self.cursor_grabbed = false;
+    }
+
+    pub fn is_cursor_grabbed(&self) -> bool {
+        self.cursor_grabbed
+    }
+
+    /// Resizes everything that depends on the window's size in physical pixels:
+    /// the layer's drawable, the depth target and the camera's aspect ratio.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        // A minimized window reports 0x0; keep the old targets until it comes back.
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.renderer.resize(size.width, size.height);
+        self.engine.resize(size.width, size.height);
+    }
+
+    /// Advances per-frame state. Called once before every `render`; input
+    /// edges and mouse motion collected since the previous call are cleared
+    /// afterwards.
+    pub fn update(&mut self) {
+        if self.cursor_grabbed && self.input.was_pressed(Action::ReleaseCursor) {
+            self.release_cursor();
+        }
+
+        let current_time = Instant::now();
+        self.engine.update(current_time.duration_since(self.last_update), &self.input);
+        self.last_update = current_time;
+
+        self.frame_count += 1;
+        let since_fps_update = current_time.duration_since(self.last_fps_update);
+        if since_fps_update >= Duration::from_secs(1) {
+            self.fps = self.frame_count as f32 / since_fps_update.as_secs_f32();
+            self.frame_count = 0;
+            self.last_fps_update = current_time;
+        }
+        self.input.end_frame();
+    }
+
+    /// Draws one frame into the window.
+    pub fn render(&mut self) {
+        self.engine.render(&mut self.renderer);
+    }
+
+    /// Takes over the calling thread and runs the event loop until the window closes.
+    pub fn run(mut self, event_loop: EventLoop<()>) -> ! {
+        event_loop.run(move |event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+
+            match &event {
+                Event::WindowEvent { event, .. } => self.input.handle_window_event(event),
+                // Raw motion arrives even over other windows; only look around
+                // while the cursor is ours.
+                Event::DeviceEvent { event, .. } if self.cursor_grabbed => self.input.handle_device_event(event),
+                _ => {}
+            }
+
+            match event {
+                Event::WindowEvent {
+                    event: WindowEvent::CloseRequested,
+                    ..
+                } => *control_flow = ControlFlow::Exit,
+                Event::WindowEvent {
+                    event: WindowEvent::Resized(size),
+                    ..
+                } => self.resize(size),
+                Event::WindowEvent {
+                    event: WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size },
+                    ..
+                } => {
+                    self.renderer.set_scale_factor(scale_factor);
+                    self.resize(*new_inner_size);
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::Focused(focused),
+                    ..
+                } => {
+                    if focused {
+                        self.grab_cursor();
+                    } else {
+                        self.release_cursor();
+                    }
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::MouseInput { state: ElementState::Pressed, .. },
+                    ..
+                } if !self.cursor_grabbed => self.grab_cursor(),
+                Event::MainEventsCleared => {
+                    // 매 프레임마다 창을 다시 그리도록 요청
+                    self.window.request_redraw();
+                }
+                Event::RedrawRequested(_) => {
+                    self.update();
+                    self.render();
+                }
+                _ => {}
+            }
+        })
+    }
+}
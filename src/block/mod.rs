@@ -0,0 +1,531 @@
+//! Block definitions and the registry that maps numeric IDs to them.
+//!
+//! The registry is loaded from `blocks.toml` (see that file for the format)
+//! and is the single source of truth for block properties used by meshing,
+//! physics, lighting and persistence.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::math::IVec3;
+use crate::toml::{parse_tables, ParseError, Table, Value};
+use crate::world::Tint;
+
+/// Numeric block ID as stored in chunks and save files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u16);
+
+impl BlockId {
+    pub const AIR: BlockId = BlockId(0);
+
+    pub fn is_air(self) -> bool {
+        self == BlockId::AIR
+    }
+}
+
+/// Index into `BlockRegistry::texture_names`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureId(pub u16);
+
+/// The six faces of a block, named after the direction they face.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Face {
+    /// +X
+    East,
+    /// -X
+    West,
+    /// +Y
+    Up,
+    /// -Y
+    Down,
+    /// +Z
+    South,
+    /// -Z
+    North,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::East, Face::West, Face::Up, Face::Down, Face::South, Face::North];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn normal(self) -> IVec3 {
+        match self {
+            Face::East => IVec3::new(1, 0, 0),
+            Face::West => IVec3::new(-1, 0, 0),
+            Face::Up => IVec3::new(0, 1, 0),
+            Face::Down => IVec3::new(0, -1, 0),
+            Face::South => IVec3::new(0, 0, 1),
+            Face::North => IVec3::new(0, 0, -1),
+        }
+    }
+
+    pub fn opposite(self) -> Face {
+        match self {
+            Face::East => Face::West,
+            Face::West => Face::East,
+            Face::Up => Face::Down,
+            Face::Down => Face::Up,
+            Face::South => Face::North,
+            Face::North => Face::South,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockDef {
+    pub id: BlockId,
+    pub name: String,
+    /// Hides the faces of neighbouring blocks and, unless `collision_height`
+    /// says otherwise, collides with entities.
+    pub solid: bool,
+    /// Lets light through and does not hide neighbouring faces.
+    pub transparent: bool,
+    /// Block light emitted, 0..=15.
+    pub light_emission: u8,
+    /// Light levels lost passing through the block, 0..=15; opaque blocks
+    /// stop light entirely.
+    pub light_opacity: u8,
+    /// Top of the collision box above the bottom of the block, 0.0..=1.0;
+    /// 0 lets entities pass through.
+    pub collision_height: f32,
+    /// Grip of the block when stood on: 1.0 is normal, lower is slipperier.
+    pub friction: f32,
+    /// Entities inside can climb up and down, like on a ladder.
+    pub climbable: bool,
+    /// Entities inside swim instead of walking.
+    pub liquid: bool,
+    /// Seconds to break by hand; negative means unbreakable.
+    pub hardness: f32,
+    /// Flat color used until textures are wired up.
+    pub color: [f32; 3],
+    /// Biome color used instead of `color`.
+    pub tint: Tint,
+    /// Texture per face, indexed by `Face::index`.
+    pub textures: [TextureId; 6],
+}
+
+impl BlockDef {
+    /// Solid and not see-through: hides neighbouring faces and blocks light.
+    pub fn is_opaque(&self) -> bool {
+        self.solid && !self.transparent
+    }
+
+    pub fn texture(&self, face: Face) -> TextureId {
+        self.textures[face.index()]
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(PathBuf, io::Error),
+    /// Malformed or inconsistent definition file; `line` is 1-based.
+    Parse { line: usize, message: String },
+}
+
+impl RegistryError {
+    fn parse(line: usize, message: impl Into<String>) -> Self {
+        RegistryError::Parse { line, message: message.into() }
+    }
+}
+
+impl From<ParseError> for RegistryError {
+    fn from(e: ParseError) -> Self {
+        RegistryError::Parse { line: e.line, message: e.message }
+    }
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            RegistryError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Maps block IDs and names to their definitions.
+#[derive(Clone, Debug)]
+pub struct BlockRegistry {
+    /// Indexed by `BlockId`; IDs do not have to be contiguous.
+    blocks: Vec<Option<BlockDef>>,
+    by_name: HashMap<String, BlockId>,
+    texture_names: Vec<String>,
+}
+
+/// Definitions shipped with the game, embedded at compile time.
+const BUILTIN_BLOCKS: &str = include_str!("../blocks.toml");
+
+impl BlockRegistry {
+    /// The registry built from the `blocks.toml` shipped with the crate.
+    pub fn builtin() -> Self {
+        Self::from_toml_str(BUILTIN_BLOCKS).unwrap_or_else(|e| panic!("blocks.toml: {}", e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let path = path.as_ref();
+        let source = read_to_string(path).map_err(|e| RegistryError::Io(path.to_owned(), e))?;
+        Self::from_toml_str(&source)
+    }
+
+    pub fn from_toml_str(source: &str) -> Result<Self, RegistryError> {
+        let mut registry = BlockRegistry {
+            blocks: Vec::new(),
+            by_name: HashMap::new(),
+            texture_names: Vec::new(),
+        };
+        for table in parse_tables(source, "block")? {
+            let def = registry.parse_block(&table)?;
+            registry.insert(def, table.line)?;
+        }
+        match registry.get(BlockId::AIR) {
+            Some(air) if !air.solid => Ok(registry),
+            _ => Err(RegistryError::parse(1, "block 0 must be defined as a non-solid air block")),
+        }
+    }
+
+    pub fn get(&self, id: BlockId) -> Option<&BlockDef> {
+        self.blocks.get(id.0 as usize).and_then(Option::as_ref)
+    }
+
+    /// Like `get`, but unknown IDs resolve to air so stale data never panics.
+    pub fn def(&self, id: BlockId) -> &BlockDef {
+        self.get(id)
+            .or_else(|| self.get(BlockId::AIR))
+            .expect("registry always contains air")
+    }
+
+    pub fn id(&self, name: &str) -> Option<BlockId> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Looks up a block by name, panicking with a clear message if it is missing.
+    pub fn expect_id(&self, name: &str) -> BlockId {
+        self.id(name).unwrap_or_else(|| panic!("block `{}` is not registered", name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BlockDef> {
+        self.blocks.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    pub fn texture_names(&self) -> &[String] {
+        &self.texture_names
+    }
+
+    pub fn texture_name(&self, texture: TextureId) -> &str {
+        &self.texture_names[texture.0 as usize]
+    }
+
+    fn insert(&mut self, def: BlockDef, line: usize) -> Result<(), RegistryError> {
+        let index = def.id.0 as usize;
+        if self.blocks.get(index).is_some_and(Option::is_some) {
+            return Err(RegistryError::parse(line, format!("duplicate block id {}", def.id.0)));
+        }
+        if self.by_name.contains_key(&def.name) {
+            return Err(RegistryError::parse(line, format!("duplicate block name `{}`", def.name)));
+        }
+        if self.blocks.len() <= index {
+            self.blocks.resize(index + 1, None);
+        }
+        self.by_name.insert(def.name.clone(), def.id);
+        self.blocks[index] = Some(def);
+        Ok(())
+    }
+
+    fn intern_texture(&mut self, name: &str) -> TextureId {
+        let index = match self.texture_names.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.texture_names.push(name.to_owned());
+                self.texture_names.len() - 1
+            }
+        };
+        TextureId(index as u16)
+    }
+
+    fn parse_block(&mut self, table: &Table) -> Result<BlockDef, RegistryError> {
+        let mut id = None;
+        let mut name = None;
+        let mut def = BlockDef {
+            id: BlockId::AIR,
+            name: String::new(),
+            solid: true,
+            transparent: false,
+            light_emission: 0,
+            light_opacity: 0,
+            collision_height: 0.0,
+            friction: 1.0,
+            climbable: false,
+            liquid: false,
+            hardness: 1.0,
+            color: [1.0; 3],
+            tint: Tint::None,
+            textures: [TextureId(0); 6],
+        };
+        // Applied in order of increasing specificity once all keys are read.
+        let mut opacity = None;
+        let mut height = None;
+        let mut texture_all = None;
+        let mut texture_groups: Vec<(&[Face], &str)> = Vec::new();
+
+        for entry in &table.entries {
+            let line = entry.line;
+            let wrong_type = |expected: &str| {
+                RegistryError::parse(
+                    line,
+                    format!("`{}` must be {}, not {}", entry.key, expected, entry.value.type_name()),
+                )
+            };
+            match (entry.key.as_str(), &entry.value) {
+                ("id", Value::Integer(v)) => {
+                    let v = u16::try_from(*v).map_err(|_| RegistryError::parse(line, "id must fit in 0..=65535"))?;
+                    id = Some(BlockId(v));
+                }
+                ("name", Value::String(v)) => name = Some(v.clone()),
+                ("solid", Value::Bool(v)) => def.solid = *v,
+                ("transparent", Value::Bool(v)) => def.transparent = *v,
+                ("light", Value::Integer(v)) => {
+                    def.light_emission = u8::try_from(*v)
+                        .ok()
+                        .filter(|v| *v <= 15)
+                        .ok_or_else(|| RegistryError::parse(line, "light must be in 0..=15"))?;
+                }
+                ("opacity", Value::Integer(v)) => {
+                    opacity = Some(
+                        u8::try_from(*v)
+                            .ok()
+                            .filter(|v| *v <= 15)
+                            .ok_or_else(|| RegistryError::parse(line, "opacity must be in 0..=15"))?,
+                    );
+                }
+                ("height", v) => {
+                    let v = v.as_f64().ok_or_else(|| wrong_type("a number"))?;
+                    if !(0.0..=1.0).contains(&v) {
+                        return Err(RegistryError::parse(line, "height must be in 0..=1"));
+                    }
+                    height = Some(v as f32);
+                }
+                ("friction", v) => {
+                    let v = v.as_f64().ok_or_else(|| wrong_type("a number"))?;
+                    if !(v.is_finite() && v > 0.0) {
+                        return Err(RegistryError::parse(line, "friction must be positive"));
+                    }
+                    def.friction = v as f32;
+                }
+                ("climbable", Value::Bool(v)) => def.climbable = *v,
+                ("liquid", Value::Bool(v)) => def.liquid = *v,
+                ("hardness", v) => {
+                    let v = v.as_f64().ok_or_else(|| wrong_type("a number"))?;
+                    if !v.is_finite() {
+                        return Err(RegistryError::parse(line, "hardness must be finite; use -1 for unbreakable"));
+                    }
+                    def.hardness = v as f32;
+                }
+                ("color", Value::Array(items)) => {
+                    let channels: Option<Vec<f64>> = items.iter().map(Value::as_f64).collect();
+                    match channels.as_deref() {
+                        Some(&[r, g, b]) => def.color = [r as f32, g as f32, b as f32],
+                        _ => return Err(RegistryError::parse(line, "color must be [r, g, b]")),
+                    }
+                }
+                ("tint", Value::String(v)) => {
+                    def.tint = match v.as_str() {
+                        "none" => Tint::None,
+                        "grass" => Tint::Grass,
+                        "foliage" => Tint::Foliage,
+                        "water" => Tint::Water,
+                        _ => return Err(RegistryError::parse(line, "tint must be none, grass, foliage or water")),
+                    };
+                }
+                ("texture", Value::String(v)) => texture_all = Some(v.as_str()),
+                ("texture_side", Value::String(v)) => {
+                    texture_groups.insert(0, (&[Face::East, Face::West, Face::South, Face::North], v.as_str()));
+                }
+                (key, Value::String(v)) if key.starts_with("texture_") => {
+                    let face: &[Face] = match &key["texture_".len()..] {
+                        "top" => &[Face::Up],
+                        "bottom" => &[Face::Down],
+                        "east" => &[Face::East],
+                        "west" => &[Face::West],
+                        "south" => &[Face::South],
+                        "north" => &[Face::North],
+                        _ => return Err(RegistryError::parse(line, format!("unknown key `{}`", key))),
+                    };
+                    texture_groups.push((face, v.as_str()));
+                }
+                ("id" | "light" | "opacity", _) => return Err(wrong_type("an integer")),
+                ("name" | "tint", _) => return Err(wrong_type("a string")),
+                ("solid" | "transparent" | "climbable" | "liquid", _) => return Err(wrong_type("a boolean")),
+                ("color", _) => return Err(wrong_type("an array")),
+                (key, _) if key.starts_with("texture") => return Err(wrong_type("a string")),
+                (key, _) => return Err(RegistryError::parse(line, format!("unknown key `{}`", key))),
+            }
+        }
+
+        def.id = id.ok_or_else(|| RegistryError::parse(table.line, "block is missing `id`"))?;
+        def.name = name.ok_or_else(|| RegistryError::parse(table.line, "block is missing `name`"))?;
+        def.light_opacity = opacity.unwrap_or(if def.is_opaque() { 15 } else { 0 });
+        def.collision_height = height.unwrap_or(if def.solid { 1.0 } else { 0.0 });
+
+        let mut faces = [texture_all; 6];
+        for (group, texture) in texture_groups {
+            for face in group {
+                faces[face.index()] = Some(texture);
+            }
+        }
+        // Faces without a texture (e.g. air) use one named after the block.
+        for (slot, texture) in def.textures.iter_mut().zip(faces) {
+            *slot = self.intern_texture(texture.unwrap_or(&def.name));
+        }
+        Ok(def)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AIR: &str = "[[block]]\nid = 0\nname = \"air\"\nsolid = false\n";
+
+    /// A registry of air plus one block whose header is on line 6 and keys start on line 7.
+    fn with_block(keys: &str) -> Result<BlockRegistry, RegistryError> {
+        BlockRegistry::from_toml_str(&format!("{}\n[[block]]\n{}", AIR, keys))
+    }
+
+    fn block(keys: &str) -> BlockDef {
+        let registry = with_block(&format!("id = 1\nname = \"test\"\n{}", keys)).unwrap();
+        registry.def(BlockId(1)).clone()
+    }
+
+    fn error(keys: &str) -> (usize, String) {
+        match with_block(&format!("id = 1\nname = \"test\"\n{}", keys)) {
+            Err(RegistryError::Parse { line, message }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn builtin_loads() {
+        let registry = BlockRegistry::builtin();
+        assert_eq!(registry.def(BlockId::AIR).name, "air");
+        assert_eq!(registry.id("stone").map(|id| registry.def(id).name.as_str()), Some("stone"));
+        // Unknown IDs read as air rather than panicking.
+        assert_eq!(registry.def(BlockId(u16::MAX)).id, BlockId::AIR);
+    }
+
+    #[test]
+    fn duplicate_id() {
+        let source = format!("{}[[block]]\nid = 0\nname = \"void\"\n", AIR);
+        let e = BlockRegistry::from_toml_str(&source).unwrap_err();
+        assert_eq!(e.to_string(), "line 5: duplicate block id 0");
+    }
+
+    #[test]
+    fn duplicate_name() {
+        let source = format!("{}[[block]]\nid = 1\nname = \"air\"\n", AIR);
+        let e = BlockRegistry::from_toml_str(&source).unwrap_err();
+        assert_eq!(e.to_string(), "line 5: duplicate block name `air`");
+    }
+
+    #[test]
+    fn air_must_be_id_0_and_not_solid() {
+        let missing = "[[block]]\nid = 1\nname = \"stone\"\n";
+        let solid = "[[block]]\nid = 0\nname = \"air\"\n";
+        for source in [missing, solid, ""] {
+            let e = BlockRegistry::from_toml_str(source).unwrap_err();
+            assert_eq!(e.to_string(), "line 1: block 0 must be defined as a non-solid air block");
+        }
+    }
+
+    #[test]
+    fn missing_id_or_name() {
+        let e = with_block("name = \"test\"").unwrap_err();
+        assert_eq!(e.to_string(), "line 6: block is missing `id`");
+        let e = with_block("id = 1").unwrap_err();
+        assert_eq!(e.to_string(), "line 6: block is missing `name`");
+    }
+
+    #[test]
+    fn wrong_value_types() {
+        assert_eq!(error("solid = 1"), (9, "`solid` must be a boolean, not integer".to_owned()));
+        assert_eq!(error("light = 1.5").1, "`light` must be an integer, not float");
+        assert_eq!(error("tint = 2").1, "`tint` must be a string, not integer");
+        assert_eq!(error("color = \"red\"").1, "`color` must be an array, not string");
+        assert_eq!(error("height = true").1, "`height` must be a number, not boolean");
+        assert_eq!(error("texture_top = []").1, "`texture_top` must be a string, not array");
+        assert_eq!(error("color = [1, 1]").1, "color must be [r, g, b]");
+        let e = with_block("id = -1\nname = \"test\"").unwrap_err();
+        assert_eq!(e.to_string(), "line 7: id must fit in 0..=65535");
+    }
+
+    #[test]
+    fn out_of_range_values() {
+        assert_eq!(error("light = 16"), (9, "light must be in 0..=15".to_owned()));
+        assert_eq!(error("light = -1").1, "light must be in 0..=15");
+        assert_eq!(error("opacity = 16").1, "opacity must be in 0..=15");
+        assert_eq!(error("height = 1.01").1, "height must be in 0..=1");
+        assert_eq!(error("height = -0.5").1, "height must be in 0..=1");
+        assert_eq!(error("friction = 0").1, "friction must be positive");
+        assert_eq!(error("friction = -1.0").1, "friction must be positive");
+        assert_eq!(error("tint = \"sky\"").1, "tint must be none, grass, foliage or water");
+    }
+
+    #[test]
+    fn non_finite_numbers() {
+        assert_eq!(error("friction = nan"), (9, "invalid value `nan`".to_owned()));
+        assert_eq!(error("hardness = inf").1, "invalid value `inf`");
+        assert_eq!(error("height = NaN").1, "invalid value `NaN`");
+        // A negative hardness is how blocks are made unbreakable.
+        assert_eq!(block("hardness = -1").hardness, -1.0);
+    }
+
+    #[test]
+    fn unknown_keys() {
+        assert_eq!(error("glow = 3"), (9, "unknown key `glow`".to_owned()));
+        assert_eq!(error("texture_up = \"x\"").1, "unknown key `texture_up`");
+    }
+
+    #[test]
+    fn opacity_and_height_defaults() {
+        let stone = block("");
+        assert_eq!((stone.light_opacity, stone.collision_height), (15, 1.0));
+        let glass = block("transparent = true");
+        assert_eq!((glass.light_opacity, glass.collision_height), (0, 1.0));
+        let flower = block("solid = false");
+        assert_eq!((flower.light_opacity, flower.collision_height), (0, 0.0));
+
+        let leaves = block("transparent = true\nopacity = 1\nheight = 0.5");
+        assert_eq!((leaves.light_opacity, leaves.collision_height), (1, 0.5));
+    }
+
+    #[test]
+    fn texture_precedence() {
+        fn face_textures(keys: &str) -> Vec<String> {
+            let registry = with_block(&format!("id = 1\nname = \"test\"\n{}", keys)).unwrap();
+            let def = registry.def(BlockId(1));
+            Face::ALL.iter().map(|&face| registry.texture_name(def.texture(face)).to_owned()).collect()
+        }
+
+        // `texture` < `texture_side` < single faces, whatever order the keys are written in.
+        let keys = "texture_east = \"east\"\ntexture_side = \"side\"\ntexture = \"all\"\ntexture_top = \"top\"";
+        assert_eq!(face_textures(keys), ["east", "side", "top", "all", "side", "side"]);
+        assert_eq!(face_textures("texture_side = \"side\""), ["side", "side", "test", "test", "side", "side"]);
+        // Without any texture keys every face is named after the block.
+        assert_eq!(face_textures(""), ["test"; 6]);
+    }
+}
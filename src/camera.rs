@@ -0,0 +1,186 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::math::{Frustum, Mat4, Vec3};
+
+/// First-person perspective camera.
+///
+/// Yaw is measured in radians around +Y with 0 looking down -Z; positive pitch
+/// looks up. Pitch is clamped just short of straight up/down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+    pub aspect: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            position: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 70f32.to_radians(),
+            near: 0.1,
+            far: 1000.0,
+            aspect: 4.0 / 3.0,
+        }
+    }
+}
+
+impl Camera {
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+
+    pub fn new(position: Vec3) -> Self {
+        Camera { position, ..Camera::default() }
+    }
+
+    /// Sets the aspect ratio from a surface size in pixels, e.g. `window.inner_size()`.
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    pub fn set_rotation(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        Vec3::new(cos_pitch * sin_yaw, sin_pitch, -cos_pitch * cos_yaw)
+    }
+
+    /// Horizontal right vector, independent of pitch.
+    pub fn right(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        Vec3::new(cos_yaw, 0.0, sin_yaw)
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_to(self.position, self.forward(), Vec3::Y)
+    }
+
+    pub fn projection_matrix(&self) -> Mat4 {
+        Mat4::perspective(self.fov_y, self.aspect, self.near, self.far)
+    }
+
+    pub fn view_projection_matrix(&self) -> Mat4 {
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    /// World-space view volume, for culling chunks before drawing them.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.view_projection_matrix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    /// Clip-space depth `z / w` of a point `distance` in front of the camera.
+    fn depth_at(camera: &Camera, distance: f32) -> f32 {
+        let clip = camera.projection_matrix().transform_vec4(Vec3::new(0.0, 0.0, -distance).extend(1.0));
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn near_and_far_planes_map_to_metal_depth_range() {
+        let camera = Camera::default();
+        assert!(depth_at(&camera, camera.near).abs() < EPSILON);
+        assert!((depth_at(&camera, camera.far) - 1.0).abs() < EPSILON);
+
+        let middle = depth_at(&camera, 10.0);
+        assert!(middle > 0.0 && middle < 1.0);
+        assert!(depth_at(&camera, 20.0) > middle, "depth must grow with distance");
+        assert!(depth_at(&camera, camera.near / 2.0) < 0.0, "in front of the near plane is clipped");
+    }
+
+    #[test]
+    fn view_projection_puts_the_look_target_at_the_center() {
+        let mut camera = Camera::new(Vec3::new(3.0, 70.0, -2.0));
+        camera.set_rotation(0.7, -0.3);
+        let target = camera.position + camera.forward() * 10.0;
+        let ndc = camera.view_projection_matrix().transform_point(target);
+        assert!(ndc.x.abs() < 1e-4 && ndc.y.abs() < 1e-4);
+        assert!((ndc.z - depth_at(&camera, 10.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn projection_keeps_the_vertical_field_of_view() {
+        let camera = Camera::default();
+        let top = Vec3::new(0.0, (camera.fov_y / 2.0).tan(), -1.0);
+        let clip = camera.projection_matrix().transform_point(top);
+        assert!((clip.y - 1.0).abs() < EPSILON && clip.x.abs() < EPSILON);
+    }
+
+    #[test]
+    fn set_viewport_updates_the_aspect_ratio() {
+        let mut camera = Camera::default();
+        camera.set_viewport(1920, 1080);
+        assert_eq!(camera.aspect, 1920.0 / 1080.0);
+
+        // A point on the right edge of a square view is outside a wide one's
+        // right edge only by the aspect ratio.
+        let right = Vec3::new(1.0, 0.0, -1.0 / (camera.fov_y / 2.0).tan());
+        let x = camera.projection_matrix().transform_point(right).x;
+        assert!((x - 1080.0 / 1920.0).abs() < EPSILON);
+
+        // A minimized window reports 0 x 0 and keeps the old ratio.
+        camera.set_viewport(0, 0);
+        assert_eq!(camera.aspect, 1920.0 / 1080.0);
+    }
+
+    #[test]
+    fn yaw_zero_looks_down_negative_z() {
+        let camera = Camera::default();
+        assert!(camera.forward().approx_eq(-Vec3::Z, EPSILON));
+        assert!(camera.right().approx_eq(Vec3::X, EPSILON));
+
+        let view = camera.view_matrix();
+        assert!(view.transform_point(Vec3::new(0.0, 0.0, -5.0)).approx_eq(Vec3::new(0.0, 0.0, -5.0), EPSILON));
+    }
+
+    #[test]
+    fn positive_yaw_turns_right() {
+        let mut camera = Camera::default();
+        camera.set_rotation(FRAC_PI_2, 0.0);
+        assert!(camera.forward().approx_eq(Vec3::X, EPSILON));
+        assert!(camera.right().approx_eq(Vec3::Z, EPSILON));
+    }
+
+    #[test]
+    fn positive_pitch_looks_up_and_is_clamped() {
+        let mut camera = Camera::default();
+        camera.set_rotation(0.0, 0.5);
+        assert!(camera.forward().y > 0.0);
+        assert!(camera.right().approx_eq(Vec3::X, EPSILON), "right ignores pitch");
+
+        camera.set_rotation(0.0, 10.0);
+        assert!(camera.pitch < FRAC_PI_2 && camera.forward().y < 1.0);
+        camera.set_rotation(0.0, -10.0);
+        assert!(camera.pitch > -FRAC_PI_2);
+        // Still a valid view matrix looking almost straight down.
+        assert!(camera.view_matrix().inverse().is_some());
+    }
+
+    #[test]
+    fn frustum_matches_the_camera() {
+        let mut camera = Camera::new(Vec3::new(0.0, 64.0, 0.0));
+        camera.set_rotation(FRAC_PI_2, 0.0);
+        let frustum = camera.frustum();
+        assert!(frustum.contains_point(Vec3::new(10.0, 64.0, 0.0)));
+        assert!(!frustum.contains_point(Vec3::new(-10.0, 64.0, 0.0)));
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use crate::camera::Camera;
+use crate::input::InputState;
+use crate::math::{IVec3, Vec3};
+use crate::mesh::Mesh;
+use crate::physics::BlockPhysics;
+use crate::player::{FixedTimestep, Player, PlayerInput};
+use crate::renderer::{MeshHandle, Renderer, Uniforms};
+use crate::sky::WorldClock;
+
+/// Ground for the player until the engine has a world: solid below y = 0.
+fn flat_ground(pos: IVec3) -> BlockPhysics {
+    if pos.y < 0 {
+        BlockPhysics::SOLID
+    } else {
+        BlockPhysics::AIR
+    }
+}
+
+/// Game-side state. Talks to the GPU only through the `Renderer` trait.
+pub struct Engine {
+    clock: WorldClock,
+    player: Player,
+    timestep: FixedTimestep,
+    /// Follows the player's eyes; updated every frame.
+    camera: Camera,
+    triangle: MeshHandle,
+}
+
+impl Engine {
+    pub fn new(renderer: &mut dyn Renderer) -> Self {
+        let triangle = renderer.upload_mesh(&Mesh::triangle());
+        let mut player = Player::new(Vec3::new(0.0, 0.0, 3.0));
+        player.pitch = -0.45;
+        let mut camera = Camera::default();
+        player.update_camera(&mut camera, 1.0);
+        Engine {
+            clock: WorldClock::default(),
+            player,
+            timestep: FixedTimestep::default(),
+            camera,
+            triangle,
+        }
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn player_mut(&mut self) -> &mut Player {
+        &mut self.player
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// The camera's position and rotation follow the player every frame;
+    /// the rest, such as the field of view, can be changed here.
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// The time of day; pause or set it to pin the sky for screenshots.
+    pub fn clock(&self) -> &WorldClock {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut WorldClock {
+        &mut self.clock
+    }
+
+    /// Called when the render target changes size, in physical pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.camera.set_viewport(width, height);
+    }
+
+    /// Advances the game by `elapsed` real time since the last update: looks
+    /// around by this frame's mouse motion, then runs the movement ticks
+    /// that are due.
+    pub fn update(&mut self, elapsed: Duration, input: &InputState) {
+        self.clock.advance(elapsed);
+
+        let (dx, dy) = input.mouse_delta();
+        self.player.look(dx, dy);
+        let player_input = PlayerInput::from_input(input);
+        for _ in 0..self.timestep.advance(elapsed) {
+            self.player.tick(&player_input, &flat_ground);
+        }
+        self.player.update_camera(&mut self.camera, self.timestep.alpha());
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer) {
+        let sky = self.clock.sky();
+        renderer.begin_frame(sky.clear_color(), &Uniforms::from_camera(&self.camera).with_sky(&sky));
+        renderer.draw(self.triangle);
+        renderer.present();
+    }
+}
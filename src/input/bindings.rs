@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::toml::{parse_entries, ParseError, Value};
+
+use super::{Action, Control};
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(PathBuf, io::Error),
+    /// Malformed bindings file; `line` is 1-based.
+    Parse { line: usize, message: String },
+}
+
+impl BindingsError {
+    fn parse(line: usize, message: impl Into<String>) -> Self {
+        BindingsError::Parse { line, message: message.into() }
+    }
+}
+
+impl From<ParseError> for BindingsError {
+    fn from(e: ParseError) -> Self {
+        BindingsError::Parse { line: e.line, message: e.message }
+    }
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingsError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            BindingsError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+/// The controls bound to each action. A control may trigger several actions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bindings {
+    controls: BTreeMap<Action, Vec<Control>>,
+}
+
+/// Default bindings shipped with the game, embedded at compile time.
+const BUILTIN_BINDINGS: &str = include_str!("../bindings.toml");
+
+/// Names of the keys that can be bound.
+const KEYS: &[(&str, VirtualKeyCode)] = {
+    use VirtualKeyCode::*;
+    &[
+        ("A", A), ("B", B), ("C", C), ("D", D), ("E", E), ("F", F), ("G", G), ("H", H), ("I", I),
+        ("J", J), ("K", K), ("L", L), ("M", M), ("N", N), ("O", O), ("P", P), ("Q", Q), ("R", R),
+        ("S", S), ("T", T), ("U", U), ("V", V), ("W", W), ("X", X), ("Y", Y), ("Z", Z),
+        ("0", Key0), ("1", Key1), ("2", Key2), ("3", Key3), ("4", Key4),
+        ("5", Key5), ("6", Key6), ("7", Key7), ("8", Key8), ("9", Key9),
+        ("Space", Space), ("Escape", Escape), ("Tab", Tab), ("Enter", Return), ("Backspace", Back),
+        ("LShift", LShift), ("RShift", RShift), ("LControl", LControl), ("RControl", RControl),
+        ("LAlt", LAlt), ("RAlt", RAlt), ("Up", Up), ("Down", Down), ("Left", Left), ("Right", Right),
+        ("F1", F1), ("F2", F2), ("F3", F3), ("F4", F4), ("F5", F5), ("F6", F6),
+        ("F7", F7), ("F8", F8), ("F9", F9), ("F10", F10), ("F11", F11), ("F12", F12),
+    ]
+};
+
+impl Control {
+    /// Parses a control name from the bindings file: a key name, `MouseLeft`,
+    /// `MouseRight`, `MouseMiddle`, `Mouse<n>` for other buttons, `WheelUp`
+    /// or `WheelDown`.
+    pub fn from_name(name: &str) -> Option<Control> {
+        match name {
+            "MouseLeft" => Some(Control::Mouse(MouseButton::Left)),
+            "MouseRight" => Some(Control::Mouse(MouseButton::Right)),
+            "MouseMiddle" => Some(Control::Mouse(MouseButton::Middle)),
+            "WheelUp" => Some(Control::WheelUp),
+            "WheelDown" => Some(Control::WheelDown),
+            _ => match name.strip_prefix("Mouse").map(str::parse) {
+                Some(Ok(button)) => Some(Control::Mouse(MouseButton::Other(button))),
+                _ => KEYS.iter().find(|(key, _)| *key == name).map(|&(_, code)| Control::Key(code)),
+            },
+        }
+    }
+
+    /// The name `from_name` accepts, or `None` for keys that cannot be bound.
+    pub fn name(self) -> Option<String> {
+        match self {
+            Control::Key(code) => KEYS.iter().find(|(_, key)| *key == code).map(|(name, _)| name.to_string()),
+            Control::Mouse(MouseButton::Left) => Some("MouseLeft".to_owned()),
+            Control::Mouse(MouseButton::Right) => Some("MouseRight".to_owned()),
+            Control::Mouse(MouseButton::Middle) => Some("MouseMiddle".to_owned()),
+            Control::Mouse(MouseButton::Other(button)) => Some(format!("Mouse{}", button)),
+            Control::WheelUp => Some("WheelUp".to_owned()),
+            Control::WheelDown => Some("WheelDown".to_owned()),
+        }
+    }
+}
+
+impl Bindings {
+    /// The bindings from the `bindings.toml` shipped with the crate.
+    pub fn builtin() -> Self {
+        Self::from_toml_str(BUILTIN_BINDINGS).unwrap_or_else(|e| panic!("bindings.toml: {}", e))
+    }
+
+    /// Loads a user's bindings file. Actions it does not mention keep their
+    /// built-in controls.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        let path = path.as_ref();
+        let source = read_to_string(path).map_err(|e| BindingsError::Io(path.to_owned(), e))?;
+        let mut bindings = Self::builtin();
+        bindings.apply(&source)?;
+        Ok(bindings)
+    }
+
+    /// Bindings with only the actions listed in `source`.
+    pub fn from_toml_str(source: &str) -> Result<Self, BindingsError> {
+        let mut bindings = Bindings::default();
+        bindings.apply(source)?;
+        Ok(bindings)
+    }
+
+    pub fn controls(&self, action: Action) -> &[Control] {
+        self.controls.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The actions `control` triggers.
+    pub fn actions(&self, control: Control) -> impl Iterator<Item = Action> + '_ {
+        self.controls
+            .iter()
+            .filter(move |(_, controls)| controls.contains(&control))
+            .map(|(&action, _)| action)
+    }
+
+    /// Adds `control` to the controls of `action`.
+    pub fn bind(&mut self, action: Action, control: Control) {
+        let controls = self.controls.entry(action).or_default();
+        if !controls.contains(&control) {
+            controls.push(control);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, control: Control) {
+        if let Some(controls) = self.controls.get_mut(&action) {
+            controls.retain(|&c| c != control);
+        }
+    }
+
+    /// Replaces every control of `action`.
+    pub fn set(&mut self, action: Action, controls: Vec<Control>) {
+        self.controls.insert(action, controls);
+    }
+
+    /// Sets the actions listed in `source`, one `action = ["Control", ...]`
+    /// line each.
+    fn apply(&mut self, source: &str) -> Result<(), BindingsError> {
+        for entry in parse_entries(source)? {
+            let line = entry.line;
+            let action = Action::from_name(&entry.key)
+                .ok_or_else(|| BindingsError::parse(line, format!("unknown action `{}`", entry.key)))?;
+            let Value::Array(items) = &entry.value else {
+                return Err(BindingsError::parse(line, format!("expected an array, found {}", entry.value.type_name())));
+            };
+            let controls = items
+                .iter()
+                .map(|item| match item {
+                    Value::String(name) => Control::from_name(name)
+                        .ok_or_else(|| BindingsError::parse(line, format!("unknown control `{}`", name))),
+                    other => Err(BindingsError::parse(line, format!("expected a string, found {}", other.type_name()))),
+                })
+                .collect::<Result<_, _>>()?;
+            self.set(action, controls);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+
+    fn parse_error(source: &str) -> (usize, String) {
+        match Bindings::from_toml_str(source) {
+            Err(BindingsError::Parse { line, message }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn builtin_binds_every_action() {
+        let bindings = Bindings::builtin();
+        for action in Action::ALL {
+            assert!(!bindings.controls(action).is_empty(), "{:?}", action);
+        }
+        assert_eq!(bindings.controls(Action::Jump), [Control::Key(VirtualKeyCode::Space)]);
+    }
+
+    #[test]
+    fn unknown_action() {
+        let (line, message) = parse_error("jump = [\"Space\"]\nfly = [\"F\"]\n");
+        assert_eq!((line, message.as_str()), (2, "unknown action `fly`"));
+    }
+
+    #[test]
+    fn unknown_control() {
+        let (line, message) = parse_error("jump = [\"Space\", \"Hyper\"]");
+        assert_eq!((line, message.as_str()), (1, "unknown control `Hyper`"));
+    }
+
+    #[test]
+    fn wrong_value_types() {
+        assert_eq!(parse_error("jump = \"Space\"").1, "expected an array, found string");
+        assert!(parse_error("jump = [1]").1.starts_with("expected a string"));
+    }
+
+    #[test]
+    fn user_file_overrides_builtin() {
+        let path = env::temp_dir().join(format!("metalcraft-bindings-{}.toml", std::process::id()));
+        fs::write(&path, "jump = [\"J\", \"MouseMiddle\"]\nsneak = []\n").unwrap();
+        let loaded = Bindings::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        let builtin = Bindings::builtin();
+        assert_eq!(
+            loaded.controls(Action::Jump),
+            [Control::Key(VirtualKeyCode::J), Control::Mouse(MouseButton::Middle)]
+        );
+        assert!(loaded.controls(Action::Sneak).is_empty());
+        for action in Action::ALL.into_iter().filter(|a| ![Action::Jump, Action::Sneak].contains(a)) {
+            assert_eq!(loaded.controls(action), builtin.controls(action), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = env::temp_dir().join("metalcraft-no-such-bindings.toml");
+        assert!(matches!(Bindings::load(&path), Err(BindingsError::Io(p, _)) if p == path));
+    }
+
+    #[test]
+    fn control_names_round_trip() {
+        let controls = KEYS.iter().map(|&(_, code)| Control::Key(code)).chain([
+            Control::Mouse(MouseButton::Left),
+            Control::Mouse(MouseButton::Right),
+            Control::Mouse(MouseButton::Middle),
+            Control::Mouse(MouseButton::Other(4)),
+            Control::WheelUp,
+            Control::WheelDown,
+        ]);
+        for control in controls {
+            let name = control.name().unwrap();
+            assert_eq!(Control::from_name(&name), Some(control), "{}", name);
+        }
+        assert_eq!(Control::Key(VirtualKeyCode::Numpad1).name(), None);
+        assert_eq!(Control::from_name("Mouse"), None);
+    }
+
+    #[test]
+    fn one_control_for_several_actions() {
+        let mut bindings = Bindings::from_toml_str("attack = [\"MouseLeft\"]").unwrap();
+        bindings.bind(Action::Use, Control::Mouse(MouseButton::Left));
+        bindings.bind(Action::Use, Control::Mouse(MouseButton::Left));
+        assert_eq!(bindings.controls(Action::Use).len(), 1);
+        let actions: Vec<_> = bindings.actions(Control::Mouse(MouseButton::Left)).collect();
+        assert_eq!(actions, [Action::Attack, Action::Use]);
+
+        bindings.unbind(Action::Attack, Control::Mouse(MouseButton::Left));
+        assert!(bindings.controls(Action::Attack).is_empty());
+    }
+}
@@ -0,0 +1,413 @@
+//! Keyboard and mouse input, mapped to game actions.
+//!
+//! Window and device events are reduced to `InputEvent`s, which
+//! `InputState` resolves through the `Bindings` into actions that can be
+//! queried as held, or as pressed or released since the last `end_frame`.
+//! Nothing here needs a window, so synthetic events work just as well.
+
+mod bindings;
+
+use std::collections::HashSet;
+
+use winit::event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+pub use self::bindings::{Bindings, BindingsError};
+
+/// Something the player can do, bound to one or more `Control`s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Sneak,
+    Sprint,
+    /// Break or hit what the crosshair is on.
+    Attack,
+    /// Place a block or use an item.
+    Use,
+    Hotbar1,
+    Hotbar2,
+    Hotbar3,
+    Hotbar4,
+    Hotbar5,
+    Hotbar6,
+    Hotbar7,
+    Hotbar8,
+    Hotbar9,
+    /// Let go of the mouse cursor.
+    ReleaseCursor,
+}
+
+impl Action {
+    pub const ALL: [Action; 19] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Sneak,
+        Action::Sprint,
+        Action::Attack,
+        Action::Use,
+        Action::Hotbar1,
+        Action::Hotbar2,
+        Action::Hotbar3,
+        Action::Hotbar4,
+        Action::Hotbar5,
+        Action::Hotbar6,
+        Action::Hotbar7,
+        Action::Hotbar8,
+        Action::Hotbar9,
+        Action::ReleaseCursor,
+    ];
+
+    /// Name used in the bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBack => "move_back",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Jump => "jump",
+            Action::Sneak => "sneak",
+            Action::Sprint => "sprint",
+            Action::Attack => "attack",
+            Action::Use => "use",
+            Action::Hotbar1 => "hotbar_1",
+            Action::Hotbar2 => "hotbar_2",
+            Action::Hotbar3 => "hotbar_3",
+            Action::Hotbar4 => "hotbar_4",
+            Action::Hotbar5 => "hotbar_5",
+            Action::Hotbar6 => "hotbar_6",
+            Action::Hotbar7 => "hotbar_7",
+            Action::Hotbar8 => "hotbar_8",
+            Action::Hotbar9 => "hotbar_9",
+            Action::ReleaseCursor => "release_cursor",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Hotbar slot selected by the action, 0..9.
+    pub fn hotbar_slot(self) -> Option<usize> {
+        let first = Action::Hotbar1 as usize;
+        (first..first + 9).contains(&(self as usize)).then(|| self as usize - first)
+    }
+}
+
+/// A physical key, button or wheel direction an action can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Control {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    /// One notch of the wheel: pressed and released at once.
+    WheelUp,
+    WheelDown,
+}
+
+/// Input that matters to the game, independent of where it came from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Press(Control),
+    Release(Control),
+    /// Raw mouse movement in device units, +Y down. Keeps coming while the
+    /// cursor is grabbed.
+    MouseMotion { dx: f64, dy: f64 },
+    /// Wheel movement in lines, positive away from the user.
+    Scroll(f32),
+    /// The window lost focus; every control is released.
+    FocusLost,
+}
+
+/// Pixels per wheel line for touchpads that report pixel deltas.
+const PIXELS_PER_LINE: f64 = 16.0;
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                let control = Control::Key(input.virtual_keycode?);
+                Some(match input.state {
+                    ElementState::Pressed => InputEvent::Press(control),
+                    ElementState::Released => InputEvent::Release(control),
+                })
+            }
+            WindowEvent::MouseInput { state, button, .. } => Some(match state {
+                ElementState::Pressed => InputEvent::Press(Control::Mouse(*button)),
+                ElementState::Released => InputEvent::Release(Control::Mouse(*button)),
+            }),
+            WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::Scroll(match *delta {
+                MouseScrollDelta::LineDelta(_, y) => y,
+                MouseScrollDelta::PixelDelta(position) => (position.y / PIXELS_PER_LINE) as f32,
+            })),
+            WindowEvent::Focused(false) => Some(InputEvent::FocusLost),
+            _ => None,
+        }
+    }
+
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match *event {
+            DeviceEvent::MouseMotion { delta: (dx, dy) } => Some(InputEvent::MouseMotion { dx, dy }),
+            _ => None,
+        }
+    }
+}
+
+/// Which actions are active, updated from `InputEvent`s.
+///
+/// An action is held while any of its controls is down. It is pressed when
+/// the first of them goes down and released when the last one comes up;
+/// those edges, the mouse motion and the scrolling are collected until
+/// `end_frame`.
+///
+/// ```
+/// use metalcraft::input::{Action, Bindings, Control, InputEvent, InputState};
+/// use winit::event::VirtualKeyCode;
+///
+/// let mut input = InputState::new(Bindings::builtin());
+/// input.handle(InputEvent::Press(Control::Key(VirtualKeyCode::W)));
+/// input.handle(InputEvent::Press(Control::Key(VirtualKeyCode::Up)));
+/// assert!(input.was_pressed(Action::MoveForward) && input.is_held(Action::MoveForward));
+///
+/// input.end_frame();
+/// input.handle(InputEvent::Release(Control::Key(VirtualKeyCode::W)));
+/// assert!(!input.was_pressed(Action::MoveForward) && !input.was_released(Action::MoveForward));
+/// input.handle(InputEvent::Release(Control::Key(VirtualKeyCode::Up)));
+/// assert!(input.was_released(Action::MoveForward) && !input.is_held(Action::MoveForward));
+///
+/// input.handle(InputEvent::Scroll(-1.0));
+/// input.handle(InputEvent::MouseMotion { dx: 3.0, dy: -2.0 });
+/// assert!(input.is_triggered(Control::WheelDown) && input.mouse_delta() == (3.0, -2.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct InputState {
+    bindings: Bindings,
+    held: HashSet<Control>,
+    /// Controls pressed since `end_frame`, including wheel notches.
+    triggered: HashSet<Control>,
+    pressed: HashSet<Action>,
+    released: HashSet<Action>,
+    mouse_delta: (f64, f64),
+    scroll: f32,
+}
+
+impl InputState {
+    pub fn new(bindings: Bindings) -> Self {
+        InputState {
+            bindings,
+            held: HashSet::new(),
+            triggered: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            mouse_delta: (0.0, 0.0),
+            scroll: 0.0,
+        }
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// Replaces the bindings, releasing everything held under the old ones.
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.release_all();
+        self.bindings = bindings;
+    }
+
+    pub fn handle(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Press(control) => self.press(control),
+            InputEvent::Release(control) => self.release(control),
+            InputEvent::MouseMotion { dx, dy } => {
+                self.mouse_delta.0 += dx;
+                self.mouse_delta.1 += dy;
+            }
+            InputEvent::Scroll(lines) if lines != 0.0 => {
+                self.scroll += lines;
+                let notch = if lines > 0.0 { Control::WheelUp } else { Control::WheelDown };
+                self.press(notch);
+                self.release(notch);
+            }
+            InputEvent::Scroll(_) => {}
+            InputEvent::FocusLost => self.release_all(),
+        }
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event) {
+            self.handle(event);
+        }
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let Some(event) = InputEvent::from_device_event(event) {
+            self.handle(event);
+        }
+    }
+
+    /// Whether any control bound to `action` is down.
+    pub fn is_held(&self, action: Action) -> bool {
+        self.bindings.controls(action).iter().any(|control| self.held.contains(control))
+    }
+
+    /// Whether `action` became held since the last `end_frame`.
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether `action` stopped being held since the last `end_frame`.
+    pub fn was_released(&self, action: Action) -> bool {
+        self.released.contains(&action)
+    }
+
+    /// Whether `control` went down since the last `end_frame`, bound or not.
+    pub fn is_triggered(&self, control: Control) -> bool {
+        self.triggered.contains(&control)
+    }
+
+    /// Mouse movement since the last `end_frame`.
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    /// Wheel lines scrolled since the last `end_frame`.
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    /// Clears the edges and motion collected so far; call once per frame
+    /// after the game has read them.
+    pub fn end_frame(&mut self) {
+        self.triggered.clear();
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll = 0.0;
+    }
+
+    /// Releases every held control, as if all keys and buttons came up.
+    pub fn release_all(&mut self) {
+        let held: Vec<_> = self.held.iter().copied().collect();
+        for control in held {
+            self.release(control);
+        }
+    }
+
+    fn press(&mut self, control: Control) {
+        self.triggered.insert(control);
+        let before = self.held_actions(control);
+        if self.held.insert(control) {
+            self.pressed.extend(self.bindings.actions(control).filter(|action| !before.contains(action)));
+        }
+    }
+
+    fn release(&mut self, control: Control) {
+        if self.held.remove(&control) {
+            let after = self.held_actions(control);
+            self.released.extend(self.bindings.actions(control).filter(|action| !after.contains(action)));
+        }
+    }
+
+    /// The actions bound to `control` that are currently held.
+    fn held_actions(&self, control: Control) -> Vec<Action> {
+        self.bindings.actions(control).filter(|&action| self.is_held(action)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: Control = Control::Key(VirtualKeyCode::W);
+    const UP: Control = Control::Key(VirtualKeyCode::Up);
+    const SPACE: Control = Control::Key(VirtualKeyCode::Space);
+
+    fn input() -> InputState {
+        InputState::new(Bindings::builtin())
+    }
+
+    #[test]
+    fn action_names_round_trip() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+        assert_eq!(Action::from_name("fly"), None);
+    }
+
+    #[test]
+    fn hotbar_slots() {
+        assert_eq!(Action::Hotbar1.hotbar_slot(), Some(0));
+        assert_eq!(Action::Hotbar5.hotbar_slot(), Some(4));
+        assert_eq!(Action::Hotbar9.hotbar_slot(), Some(8));
+        assert_eq!(Action::Use.hotbar_slot(), None);
+        assert_eq!(Action::ReleaseCursor.hotbar_slot(), None);
+        let slots: Vec<_> = Action::ALL.into_iter().filter_map(Action::hotbar_slot).collect();
+        assert_eq!(slots, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn focus_lost_releases_held_actions() {
+        let mut input = input();
+        input.handle(InputEvent::Press(W));
+        input.handle(InputEvent::Press(SPACE));
+        input.end_frame();
+
+        input.handle(InputEvent::FocusLost);
+        assert!(!input.is_held(Action::MoveForward) && !input.is_held(Action::Jump));
+        assert!(input.was_released(Action::MoveForward) && input.was_released(Action::Jump));
+
+        // The keys coming up afterwards change nothing.
+        input.end_frame();
+        input.handle(InputEvent::Release(W));
+        assert!(!input.was_released(Action::MoveForward));
+    }
+
+    #[test]
+    fn wheel_notches_last_until_end_frame() {
+        let mut input = input();
+        input.handle(InputEvent::Scroll(2.0));
+        input.handle(InputEvent::Scroll(-0.5));
+        assert!(input.is_triggered(Control::WheelUp) && input.is_triggered(Control::WheelDown));
+        assert_eq!(input.scroll(), 1.5);
+
+        input.end_frame();
+        assert!(!input.is_triggered(Control::WheelUp) && !input.is_triggered(Control::WheelDown));
+        assert_eq!(input.scroll(), 0.0);
+
+        input.handle(InputEvent::Scroll(0.0));
+        assert!(!input.is_triggered(Control::WheelUp) && !input.is_triggered(Control::WheelDown));
+    }
+
+    #[test]
+    fn bound_wheel_presses_and_releases_in_one_frame() {
+        let mut bindings = Bindings::builtin();
+        bindings.bind(Action::Hotbar2, Control::WheelDown);
+        let mut input = InputState::new(bindings);
+        input.handle(InputEvent::Scroll(-1.0));
+        assert!(input.was_pressed(Action::Hotbar2) && input.was_released(Action::Hotbar2));
+        assert!(!input.is_held(Action::Hotbar2));
+    }
+
+    #[test]
+    fn action_held_while_any_control_is_down() {
+        let mut input = input();
+        input.handle(InputEvent::Press(W));
+        input.handle(InputEvent::Press(UP));
+        input.handle(InputEvent::Release(W));
+        assert!(input.is_held(Action::MoveForward));
+        assert!(input.was_pressed(Action::MoveForward) && !input.was_released(Action::MoveForward));
+    }
+
+    #[test]
+    fn rebinding_releases_everything() {
+        let mut input = input();
+        input.handle(InputEvent::Press(SPACE));
+        input.set_bindings(Bindings::from_toml_str("jump = [\"J\"]").unwrap());
+        assert!(!input.is_held(Action::Jump));
+        assert!(input.was_released(Action::Jump));
+    }
+}
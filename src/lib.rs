@@ -0,0 +1,27 @@
+#[cfg(target_os = "macos")]
+#[macro_use]
+extern crate objc;
+
+#[cfg(target_os = "macos")]
+pub mod app;
+pub mod block;
+pub mod camera;
+pub mod engine;
+pub mod input;
+pub mod math;
+pub mod mesh;
+pub mod mesher;
+pub mod physics;
+pub mod platform;
+pub mod player;
+pub mod renderer;
+pub mod sky;
+mod toml;
+pub mod vertex;
+pub mod world;
+pub mod worldgen;
+
+#[cfg(target_os = "macos")]
+pub use app::App;
+pub use engine::Engine;
+pub use renderer::Renderer;
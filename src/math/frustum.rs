@@ -0,0 +1,130 @@
+use super::{Mat4, Vec3, Vec4};
+
+/// Plane `normal . p + d = 0`, with the normal pointing into the kept half-space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl Plane {
+    fn from_vec4(v: Vec4) -> Plane {
+        let length = v.truncate().length();
+        Plane {
+            normal: v.truncate() / length,
+            d: v.w / length,
+        }
+    }
+
+    /// Signed distance; positive on the inside.
+    pub fn distance(&self, p: Vec3) -> f32 {
+        self.normal.dot(p) + self.d
+    }
+}
+
+/// The six planes of a view volume, extracted from a view-projection matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near, far.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from `projection * view` (Gribb/Hartmann), assuming
+    /// Metal's clip volume `-w <= x, y <= w`, `0 <= z <= w`.
+    pub fn from_matrix(view_projection: &Mat4) -> Frustum {
+        let m = view_projection;
+        let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        Frustum {
+            planes: [
+                Plane::from_vec4(r3 + r0),
+                Plane::from_vec4(r3 - r0),
+                Plane::from_vec4(r3 + r1),
+                Plane::from_vec4(r3 - r1),
+                Plane::from_vec4(r2),
+                Plane::from_vec4(r3 - r2),
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.distance(p) >= 0.0)
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.distance(center) >= -radius)
+    }
+
+    /// Conservative box test: false only if the box is entirely outside one plane.
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            let n = plane.normal;
+            let farthest = Vec3::new(
+                if n.x >= 0.0 { max.x } else { min.x },
+                if n.y >= 0.0 { max.y } else { min.y },
+                if n.z >= 0.0 { max.z } else { min.z },
+            );
+            plane.distance(farthest) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    /// 90 degree square view from the origin down -Z, depth 1..100.
+    fn frustum() -> Frustum {
+        Frustum::from_matrix(&Mat4::perspective(FRAC_PI_2, 1.0, 1.0, 100.0))
+    }
+
+    #[test]
+    fn contains_points_inside_only() {
+        let frustum = frustum();
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -10.0)));
+        assert!(frustum.contains_point(Vec3::new(9.0, -9.0, -10.0)));
+        assert!(!frustum.contains_point(Vec3::new(11.0, 0.0, -10.0)), "right of the view");
+        assert!(!frustum.contains_point(Vec3::new(0.0, 11.0, -10.0)), "above the view");
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 10.0)), "behind the camera");
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -0.5)), "before the near plane");
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -101.0)), "past the far plane");
+    }
+
+    #[test]
+    fn planes_are_normalized() {
+        for plane in frustum().planes {
+            assert!((plane.normal.length() - 1.0).abs() < 1e-5);
+        }
+        // Near plane at z = -1, normal pointing away from the camera.
+        let near = frustum().planes[4];
+        assert!((near.distance(Vec3::new(0.0, 0.0, -3.0)) - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn culls_boxes_outside_one_plane() {
+        let frustum = frustum();
+        let unit = |min: Vec3| (min, min + Vec3::ONE);
+        let cases = [
+            (unit(Vec3::new(-0.5, -0.5, -20.0)), true),
+            // Straddling the right plane still counts.
+            (unit(Vec3::new(19.5, 0.0, -20.0)), true),
+            (unit(Vec3::new(25.0, 0.0, -20.0)), false),
+            (unit(Vec3::new(0.0, 0.0, 5.0)), false),
+            (unit(Vec3::new(0.0, 0.0, -150.0)), false),
+        ];
+        for ((min, max), visible) in cases {
+            assert_eq!(frustum.intersects_aabb(min, max), visible, "box at {:?}", min);
+        }
+        // A box around the camera is visible.
+        assert!(frustum.intersects_aabb(Vec3::splat(-5.0), Vec3::splat(5.0)));
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = frustum();
+        assert!(frustum.intersects_sphere(Vec3::new(12.0, 0.0, -10.0), 2.0));
+        assert!(!frustum.intersects_sphere(Vec3::new(15.0, 0.0, -10.0), 2.0));
+    }
+}
@@ -0,0 +1,334 @@
+use std::ops::Mul;
+
+use super::{Quat, Vec3, Vec4};
+
+/// Column-major 4x4 matrix, `cols[c][r]`. Same memory layout as Metal's `float4x4`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    pub cols: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        cols: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub const fn from_cols(cols: [[f32; 4]; 4]) -> Mat4 {
+        Mat4 { cols }
+    }
+
+    pub fn translation(t: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY;
+        m.cols[3] = [t.x, t.y, t.z, 1.0];
+        m
+    }
+
+    pub fn scale(s: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY;
+        m.cols[0][0] = s.x;
+        m.cols[1][1] = s.y;
+        m.cols[2][2] = s.z;
+        m
+    }
+
+    pub fn from_quat(q: Quat) -> Mat4 {
+        let Quat { x, y, z, w } = q;
+        let (x2, y2, z2) = (x + x, y + y, z + z);
+        let (xx, yy, zz) = (x * x2, y * y2, z * z2);
+        let (xy, xz, yz) = (x * y2, x * z2, y * z2);
+        let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+        Mat4 {
+            cols: [
+                [1.0 - (yy + zz), xy + wz, xz - wy, 0.0],
+                [xy - wz, 1.0 - (xx + zz), yz + wx, 0.0],
+                [xz + wy, yz - wx, 1.0 - (xx + yy), 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Right-handed perspective projection (camera looks down -Z) that maps
+    /// depth to Metal's 0..1 range: `near` to 0.0 and `far` to 1.0.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        let range = 1.0 / (near - far);
+        Mat4 {
+            cols: [
+                [f / aspect, 0.0, 0.0, 0.0],
+                [0.0, f, 0.0, 0.0],
+                [0.0, 0.0, far * range, -1.0],
+                [0.0, 0.0, near * far * range, 0.0],
+            ],
+        }
+    }
+
+    /// Right-handed orthographic projection mapping `-near..-far` along Z to depth 0..1.
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+        let rml = 1.0 / (right - left);
+        let tmb = 1.0 / (top - bottom);
+        let range = 1.0 / (near - far);
+        Mat4 {
+            cols: [
+                [2.0 * rml, 0.0, 0.0, 0.0],
+                [0.0, 2.0 * tmb, 0.0, 0.0],
+                [0.0, 0.0, range, 0.0],
+                [-(right + left) * rml, -(top + bottom) * tmb, near * range, 1.0],
+            ],
+        }
+    }
+
+    /// Right-handed view matrix for an eye at `eye` looking along `forward`.
+    pub fn look_to(eye: Vec3, forward: Vec3, up: Vec3) -> Mat4 {
+        let f = forward.normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+        Mat4 {
+            cols: [
+                [s.x, u.x, -f.x, 0.0],
+                [s.y, u.y, -f.y, 0.0],
+                [s.z, u.z, -f.z, 0.0],
+                [-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0],
+            ],
+        }
+    }
+
+    /// Right-handed view matrix for an eye at `eye` looking at `target`.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+        Mat4::look_to(eye, target - eye, up)
+    }
+
+    pub fn col(&self, c: usize) -> Vec4 {
+        Vec4::from(self.cols[c])
+    }
+
+    pub fn row(&self, r: usize) -> Vec4 {
+        Vec4::new(self.cols[0][r], self.cols[1][r], self.cols[2][r], self.cols[3][r])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        Mat4 {
+            cols: [0, 1, 2, 3].map(|c| self.row(c).to_array()),
+        }
+    }
+
+    pub fn transform(&self, v: [f32; 4]) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for (c, col) in self.cols.iter().enumerate() {
+            for r in 0..4 {
+                out[r] += col[r] * v[c];
+            }
+        }
+        out
+    }
+
+    pub fn transform_vec4(&self, v: Vec4) -> Vec4 {
+        Vec4::from(self.transform(v.to_array()))
+    }
+
+    /// Transforms a point (w = 1), dividing by the resulting w.
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let v = self.transform_vec4(p.extend(1.0));
+        v.truncate() / v.w
+    }
+
+    /// Transforms a direction (w = 0); translation is ignored.
+    pub fn transform_direction(&self, d: Vec3) -> Vec3 {
+        self.transform_vec4(d.extend(0.0)).truncate()
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (_, det) = self.adjugate();
+        det
+    }
+
+    /// The inverse matrix, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let (adjugate, det) = self.adjugate();
+        if det.abs() <= f32::EPSILON * 1e-3 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        Some(Mat4 {
+            cols: adjugate.map(|col| col.map(|v| v * inv_det)),
+        })
+    }
+
+    /// Cofactor expansion; returns the adjugate (column-major) and the determinant.
+    fn adjugate(&self) -> ([[f32; 4]; 4], f32) {
+        // Flatten column-major so m[c * 4 + r] == cols[c][r].
+        let m: [f32; 16] = std::array::from_fn(|i| self.cols[i / 4][i % 4]);
+        let mut inv = [0.0f32; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14] + m[13] * m[6] * m[11] - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14] - m[12] * m[6] * m[11] + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13] + m[12] * m[5] * m[11] - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13] - m[12] * m[5] * m[10] + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14] - m[13] * m[2] * m[11] + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14] + m[12] * m[2] * m[11] - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13] - m[12] * m[1] * m[11] + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13] + m[12] * m[1] * m[10] - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14] + m[13] * m[2] * m[7] - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14] - m[12] * m[2] * m[7] + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13] + m[12] * m[1] * m[7] - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13] - m[12] * m[1] * m[6] + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10] - m[9] * m[2] * m[7] + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10] + m[8] * m[2] * m[7] - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9] - m[8] * m[1] * m[7] + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9] + m[8] * m[1] * m[6] - m[8] * m[2] * m[5];
+
+        let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+        let mut cols = [[0.0; 4]; 4];
+        for (i, v) in inv.iter().enumerate() {
+            cols[i / 4][i % 4] = *v;
+        }
+        (cols, det)
+    }
+
+    /// True if every element is within `epsilon` of `o`.
+    pub fn approx_eq(&self, o: &Mat4, epsilon: f32) -> bool {
+        (0..4).all(|c| self.col(c).approx_eq(o.col(c), epsilon))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::IDENTITY
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        Mat4 {
+            cols: rhs.cols.map(|col| self.transform(col)),
+        }
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+    fn mul(self, v: Vec4) -> Vec4 {
+        self.transform_vec4(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    /// An arbitrary invertible transform with rotation, scale and translation.
+    fn transform() -> Mat4 {
+        Mat4::translation(Vec3::new(3.0, -2.0, 7.5))
+            * Quat::from_axis_angle(Vec3::new(1.0, 2.0, -0.5), 0.8).to_mat4()
+            * Mat4::scale(Vec3::new(2.0, 0.5, 1.5))
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        for m in [transform(), Mat4::perspective(1.2, 1.5, 0.1, 500.0), Mat4::IDENTITY] {
+            let inverse = m.inverse().expect("matrix is invertible");
+            assert!((inverse * m).approx_eq(&Mat4::IDENTITY, 1e-4), "{:?}", inverse * m);
+            assert!((m * inverse).approx_eq(&Mat4::IDENTITY, 1e-4), "{:?}", m * inverse);
+        }
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert_eq!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        // Two equal columns.
+        let m = Mat4::from_cols([[1.0, 2.0, 3.0, 0.0], [1.0, 2.0, 3.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]);
+        assert_eq!(m.inverse(), None);
+        assert_eq!(m.determinant(), 0.0);
+    }
+
+    #[test]
+    fn determinant_of_scale() {
+        assert!((Mat4::scale(Vec3::new(2.0, 3.0, 4.0)).determinant() - 24.0).abs() < EPSILON);
+        assert!((transform().determinant() - 1.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let m = transform();
+        assert_eq!(m.transpose().transpose(), m);
+        assert_eq!(m.transpose().row(1), m.col(1));
+    }
+
+    #[test]
+    fn points_and_directions() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(m.transform_point(Vec3::ZERO), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(m.transform_direction(Vec3::X), Vec3::X);
+    }
+
+    #[test]
+    fn look_at_moves_the_target_onto_negative_z() {
+        let eye = Vec3::new(4.0, 5.0, 6.0);
+        let target = Vec3::new(-2.0, 1.0, 0.5);
+        let view = Mat4::look_at(eye, target, Vec3::Y);
+        assert!(view.transform_point(eye).approx_eq(Vec3::ZERO, 1e-4));
+        let distance = (target - eye).length();
+        assert!(view.transform_point(target).approx_eq(Vec3::new(0.0, 0.0, -distance), 1e-4));
+        // The view is a rigid transform: it keeps lengths and up stays up.
+        assert!((view.transform_direction(Vec3::X).length() - 1.0).abs() < EPSILON);
+        assert!(view.transform_direction(Vec3::Y).y > 0.0);
+    }
+
+    #[test]
+    fn look_at_and_look_to_agree() {
+        let eye = Vec3::new(1.0, 2.0, 3.0);
+        let forward = Vec3::new(0.3, -0.2, -1.0);
+        assert!(Mat4::look_at(eye, eye + forward, Vec3::Y).approx_eq(&Mat4::look_to(eye, forward, Vec3::Y), EPSILON));
+    }
+
+    #[test]
+    fn orthographic_maps_the_box_to_clip_space() {
+        let m = Mat4::orthographic(-4.0, 8.0, -1.0, 5.0, 0.5, 100.0);
+        assert!(m.transform_point(Vec3::new(-4.0, -1.0, -0.5)).approx_eq(Vec3::new(-1.0, -1.0, 0.0), EPSILON));
+        assert!(m.transform_point(Vec3::new(8.0, 5.0, -100.0)).approx_eq(Vec3::new(1.0, 1.0, 1.0), EPSILON));
+        assert!(m.transform_point(Vec3::new(2.0, 2.0, -50.25)).approx_eq(Vec3::new(0.0, 0.0, 0.5), EPSILON));
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_zero_and_one() {
+        let m = Mat4::perspective(FRAC_PI_2, 2.0, 0.5, 200.0);
+        assert!(m.transform_point(Vec3::new(0.0, 0.0, -0.5)).z.abs() < EPSILON);
+        assert!((m.transform_point(Vec3::new(0.0, 0.0, -200.0)).z - 1.0).abs() < EPSILON);
+        // 90 degrees vertically: y = -z lands on the top edge; x is squeezed by the aspect.
+        assert!(m.transform_point(Vec3::new(2.0, 1.0, -1.0)).approx_eq(Vec3::new(1.0, 1.0, m.transform_point(Vec3::new(0.0, 0.0, -1.0)).z), EPSILON));
+    }
+
+    #[test]
+    fn multiplication_applies_the_right_matrix_first() {
+        let t = Mat4::translation(Vec3::X);
+        let s = Mat4::scale(Vec3::splat(2.0));
+        assert_eq!((t * s).transform_point(Vec3::X), Vec3::new(3.0, 0.0, 0.0));
+        assert_eq!((s * t).transform_point(Vec3::X), Vec3::new(4.0, 0.0, 0.0));
+    }
+}
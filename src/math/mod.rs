@@ -0,0 +1,51 @@
+//! Small linear algebra library for the renderer, world and physics.
+//!
+//! All types are `#[repr(C)]` so they can be copied straight into Metal
+//! buffers. `Vec2`/`Vec4`/`Mat4` match `float2`/`float4`/`float4x4`; `Vec3` is
+//! 12 bytes and matches `packed_float3` (a plain `float3` in a Metal struct is
+//! padded to 16 bytes). Matrices are column-major and use right-handed
+//! coordinates with Metal's 0..1 clip-space depth.
+
+mod frustum;
+mod mat;
+mod quat;
+mod vec;
+
+pub use self::frustum::{Frustum, Plane};
+pub use self::mat::Mat4;
+pub use self::quat::Quat;
+pub use self::vec::{IVec3, Vec2, Vec3, Vec4};
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{align_of, offset_of, size_of};
+
+    use super::*;
+
+    #[test]
+    fn layouts_match_metal() {
+        // (size, alignment) of float2, packed_float3, float4, float4x4 and
+        // int3 as laid out in a plain buffer.
+        assert_eq!((size_of::<Vec2>(), align_of::<Vec2>()), (8, 4));
+        assert_eq!((size_of::<Vec3>(), align_of::<Vec3>()), (12, 4));
+        assert_eq!((size_of::<Vec4>(), align_of::<Vec4>()), (16, 4));
+        assert_eq!((size_of::<Quat>(), align_of::<Quat>()), (16, 4));
+        assert_eq!((size_of::<Mat4>(), align_of::<Mat4>()), (64, 4));
+        assert_eq!((size_of::<IVec3>(), align_of::<IVec3>()), (12, 4));
+    }
+
+    #[test]
+    fn fields_are_in_declaration_order() {
+        assert_eq!([offset_of!(Vec3, x), offset_of!(Vec3, y), offset_of!(Vec3, z)], [0, 4, 8]);
+        assert_eq!([offset_of!(Vec4, x), offset_of!(Vec4, w)], [0, 12]);
+        assert_eq!([offset_of!(Quat, x), offset_of!(Quat, w)], [0, 12]);
+    }
+
+    #[test]
+    fn matrices_are_column_major_in_memory() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        // SAFETY: `Mat4` is `#[repr(C)]` around `[[f32; 4]; 4]`.
+        let floats: [f32; 16] = unsafe { std::mem::transmute(m) };
+        assert_eq!(&floats[12..], &[1.0, 2.0, 3.0, 1.0]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::ops::Mul;
+
+use super::{Mat4, Vec3};
+
+/// Rotation quaternion, `w` last to match Metal's `float4` / `simd_quatf`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
+
+    pub const fn from_xyzw(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Quat { x, y, z, w }
+    }
+
+    /// Rotation of `angle` radians around `axis` (right-handed).
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let axis = axis.normalize();
+        let (s, c) = (angle * 0.5).sin_cos();
+        Quat::from_xyzw(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    /// Camera-style rotation: pitch around X, then yaw around Y. Yaw 0 keeps -Z forward.
+    pub fn from_yaw_pitch(yaw: f32, pitch: f32) -> Self {
+        Quat::from_axis_angle(Vec3::Y, -yaw) * Quat::from_axis_angle(Vec3::X, pitch)
+    }
+
+    pub fn dot(self, o: Quat) -> f32 {
+        self.x * o.x + self.y * o.y + self.z * o.z + self.w * o.w
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Quat {
+        let inv = 1.0 / self.length();
+        Quat::from_xyzw(self.x * inv, self.y * inv, self.z * inv, self.w * inv)
+    }
+
+    pub fn conjugate(self) -> Quat {
+        Quat::from_xyzw(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(self) -> Quat {
+        let inv = 1.0 / self.dot(self);
+        let c = self.conjugate();
+        Quat::from_xyzw(c.x * inv, c.y * inv, c.z * inv, c.w * inv)
+    }
+
+    pub fn rotate(self, v: Vec3) -> Vec3 {
+        // v' = v + 2w(q x v) + 2q x (q x v)
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = q.cross(v) * 2.0;
+        v + t * self.w + q.cross(t)
+    }
+
+    /// Spherical interpolation along the shortest arc.
+    pub fn slerp(self, mut o: Quat, t: f32) -> Quat {
+        let mut cos = self.dot(o);
+        if cos < 0.0 {
+            o = Quat::from_xyzw(-o.x, -o.y, -o.z, -o.w);
+            cos = -cos;
+        }
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quat::from_xyzw(
+            self.x * a + o.x * b,
+            self.y * a + o.y * b,
+            self.z * a + o.z * b,
+            self.w * a + o.w * b,
+        )
+        .normalize()
+    }
+
+    pub fn to_mat4(self) -> Mat4 {
+        Mat4::from_quat(self)
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Quat::IDENTITY
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+    /// Hamilton product: `a * b` applies `b` first, then `a`.
+    fn mul(self, o: Quat) -> Quat {
+        Quat::from_xyzw(
+            self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+            self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+            self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+            self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z,
+        )
+    }
+}
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+    fn mul(self, v: Vec3) -> Vec3 {
+        self.rotate(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn approx_eq(a: Quat, b: Quat) -> bool {
+        // q and -q are the same rotation.
+        (a.dot(b).abs() - 1.0).abs() < EPSILON
+    }
+
+    #[test]
+    fn rotation_matches_the_matrix() {
+        let q = Quat::from_axis_angle(Vec3::new(1.0, -2.0, 0.5), 1.1);
+        let m = q.to_mat4();
+        for v in [Vec3::X, Vec3::Y, Vec3::Z, Vec3::new(3.0, -1.0, 2.0)] {
+            assert!(q.rotate(v).approx_eq(m.transform_direction(v), 1e-4), "{:?}", v);
+            assert!((q * v).approx_eq(m.transform_point(v), 1e-4), "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn axis_angle_is_right_handed() {
+        let q = Quat::from_axis_angle(Vec3::Z, FRAC_PI_2);
+        assert!(q.rotate(Vec3::X).approx_eq(Vec3::Y, EPSILON));
+        let q = Quat::from_axis_angle(Vec3::Y, FRAC_PI_2);
+        assert!(q.rotate(Vec3::Z).approx_eq(Vec3::X, EPSILON));
+    }
+
+    #[test]
+    fn product_applies_the_right_rotation_first() {
+        let a = Quat::from_axis_angle(Vec3::Z, FRAC_PI_2);
+        let b = Quat::from_axis_angle(Vec3::X, FRAC_PI_2);
+        let v = Vec3::Y;
+        assert!((a * b).rotate(v).approx_eq(a.rotate(b.rotate(v)), EPSILON));
+        assert!(((a * b).to_mat4()).approx_eq(&(a.to_mat4() * b.to_mat4()), EPSILON));
+    }
+
+    #[test]
+    fn inverse_undoes_the_rotation() {
+        let q = Quat::from_axis_angle(Vec3::new(0.2, 1.0, 0.3), 2.0);
+        assert!(approx_eq(q * q.inverse(), Quat::IDENTITY));
+        assert!(q.inverse().rotate(q.rotate(Vec3::X)).approx_eq(Vec3::X, EPSILON));
+    }
+
+    #[test]
+    fn yaw_pitch_matches_the_camera_convention() {
+        // Yaw 0 keeps looking down -Z; positive yaw turns right towards +X.
+        assert!(Quat::from_yaw_pitch(0.0, 0.0).rotate(-Vec3::Z).approx_eq(-Vec3::Z, EPSILON));
+        assert!(Quat::from_yaw_pitch(FRAC_PI_2, 0.0).rotate(-Vec3::Z).approx_eq(Vec3::X, EPSILON));
+        assert!(Quat::from_yaw_pitch(0.0, 0.5).rotate(-Vec3::Z).y > 0.0);
+    }
+
+    #[test]
+    fn slerp_endpoints_and_midpoint() {
+        let a = Quat::from_axis_angle(Vec3::Y, 0.2);
+        let b = Quat::from_axis_angle(Vec3::Y, 1.4);
+        assert!(approx_eq(a.slerp(b, 0.0), a));
+        assert!(approx_eq(a.slerp(b, 1.0), b));
+        assert!(approx_eq(a.slerp(b, 0.5), Quat::from_axis_angle(Vec3::Y, 0.8)));
+        assert!((a.slerp(b, 0.3).length() - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn slerp_takes_the_shortest_arc() {
+        let a = Quat::from_axis_angle(Vec3::Y, 0.1);
+        let b = Quat::from_axis_angle(Vec3::Y, 2.0 * PI - 0.1);
+        // Halfway between +0.1 and -0.1 radians is no rotation, not half a turn.
+        assert!(approx_eq(a.slerp(b, 0.5), Quat::IDENTITY));
+    }
+}
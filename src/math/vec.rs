@@ -0,0 +1,339 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+
+macro_rules! impl_vec_ops {
+    ($ty:ident, $scalar:ty, $($field:ident),+) => {
+        impl Add for $ty {
+            type Output = $ty;
+            fn add(self, o: $ty) -> $ty {
+                $ty { $($field: self.$field + o.$field),+ }
+            }
+        }
+
+        impl Sub for $ty {
+            type Output = $ty;
+            fn sub(self, o: $ty) -> $ty {
+                $ty { $($field: self.$field - o.$field),+ }
+            }
+        }
+
+        impl Mul<$scalar> for $ty {
+            type Output = $ty;
+            fn mul(self, s: $scalar) -> $ty {
+                $ty { $($field: self.$field * s),+ }
+            }
+        }
+
+        impl Mul<$ty> for $scalar {
+            type Output = $ty;
+            fn mul(self, v: $ty) -> $ty {
+                v * self
+            }
+        }
+
+        impl Div<$scalar> for $ty {
+            type Output = $ty;
+            fn div(self, s: $scalar) -> $ty {
+                $ty { $($field: self.$field / s),+ }
+            }
+        }
+
+        impl Neg for $ty {
+            type Output = $ty;
+            fn neg(self) -> $ty {
+                $ty { $($field: -self.$field),+ }
+            }
+        }
+
+        impl AddAssign for $ty {
+            fn add_assign(&mut self, o: $ty) {
+                *self = *self + o;
+            }
+        }
+
+        impl SubAssign for $ty {
+            fn sub_assign(&mut self, o: $ty) {
+                *self = *self - o;
+            }
+        }
+
+        impl MulAssign<$scalar> for $ty {
+            fn mul_assign(&mut self, s: $scalar) {
+                *self = *self * s;
+            }
+        }
+
+        impl DivAssign<$scalar> for $ty {
+            fn div_assign(&mut self, s: $scalar) {
+                *self = *self / s;
+            }
+        }
+
+        impl $ty {
+            /// Component-wise product.
+            pub fn mul_elem(self, o: $ty) -> $ty {
+                $ty { $($field: self.$field * o.$field),+ }
+            }
+
+            pub fn dot(self, o: $ty) -> $scalar {
+                let mut sum: $scalar = Default::default();
+                $(sum += self.$field * o.$field;)+
+                sum
+            }
+        }
+    };
+}
+
+macro_rules! impl_float_vec {
+    ($ty:ident, $($field:ident),+) => {
+        impl_vec_ops!($ty, f32, $($field),+);
+
+        impl $ty {
+            pub fn length(self) -> f32 {
+                self.dot(self).sqrt()
+            }
+
+            pub fn length_squared(self) -> f32 {
+                self.dot(self)
+            }
+
+            /// Unit vector in the same direction. Returns zero for a zero vector.
+            pub fn normalize(self) -> $ty {
+                let length = self.length();
+                if length > 0.0 { self / length } else { self }
+            }
+
+            pub fn lerp(self, o: $ty, t: f32) -> $ty {
+                self + (o - self) * t
+            }
+
+            pub fn min(self, o: $ty) -> $ty {
+                $ty { $($field: self.$field.min(o.$field)),+ }
+            }
+
+            pub fn max(self, o: $ty) -> $ty {
+                $ty { $($field: self.$field.max(o.$field)),+ }
+            }
+
+            pub fn abs(self) -> $ty {
+                $ty { $($field: self.$field.abs()),+ }
+            }
+
+            pub fn floor(self) -> $ty {
+                $ty { $($field: self.$field.floor()),+ }
+            }
+
+            /// True if every component is within `epsilon` of `o`.
+            pub fn approx_eq(self, o: $ty, epsilon: f32) -> bool {
+                true $(&& (self.$field - o.$field).abs() <= epsilon)+
+            }
+        }
+    };
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+/// Integer block/chunk coordinate.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IVec3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl_float_vec!(Vec2, x, y);
+impl_float_vec!(Vec3, x, y, z);
+impl_float_vec!(Vec4, x, y, z, w);
+impl_vec_ops!(IVec3, i32, x, y, z);
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2::new(0.0, 0.0);
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Vec2 { x, y }
+    }
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+    pub const ONE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+    pub const X: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+    pub const Y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+    pub const Z: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Vec3 { x, y, z }
+    }
+
+    pub const fn splat(v: f32) -> Self {
+        Vec3::new(v, v, v)
+    }
+
+    pub fn cross(self, o: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * o.z - self.z * o.y,
+            self.z * o.x - self.x * o.z,
+            self.x * o.y - self.y * o.x,
+        )
+    }
+
+    pub fn extend(self, w: f32) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+
+    /// The block containing this point.
+    pub fn to_ivec3(self) -> IVec3 {
+        IVec3::new(self.x.floor() as i32, self.y.floor() as i32, self.z.floor() as i32)
+    }
+
+    pub fn to_array(self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl Vec4 {
+    pub const ZERO: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Vec4 { x, y, z, w }
+    }
+
+    pub fn truncate(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+impl From<[f32; 3]> for Vec3 {
+    fn from(a: [f32; 3]) -> Self {
+        Vec3::new(a[0], a[1], a[2])
+    }
+}
+
+impl From<[f32; 4]> for Vec4 {
+    fn from(a: [f32; 4]) -> Self {
+        Vec4::new(a[0], a[1], a[2], a[3])
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = f32;
+    fn index(&self, i: usize) -> &f32 {
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index {} out of range", i),
+        }
+    }
+}
+
+impl IVec3 {
+    pub const ZERO: IVec3 = IVec3::new(0, 0, 0);
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        IVec3 { x, y, z }
+    }
+
+    pub fn min_elem(self, o: IVec3) -> IVec3 {
+        IVec3::new(self.x.min(o.x), self.y.min(o.y), self.z.min(o.z))
+    }
+
+    pub fn max_elem(self, o: IVec3) -> IVec3 {
+        IVec3::new(self.x.max(o.x), self.y.max(o.y), self.z.max(o.z))
+    }
+
+    pub fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.x as f32, self.y as f32, self.z as f32)
+    }
+
+    /// Component-wise floored division, e.g. block position to chunk position.
+    pub fn div_euclid(self, d: i32) -> IVec3 {
+        IVec3::new(self.x.div_euclid(d), self.y.div_euclid(d), self.z.div_euclid(d))
+    }
+
+    /// Component-wise non-negative remainder, e.g. block position within its chunk.
+    pub fn rem_euclid(self, d: i32) -> IVec3 {
+        IVec3::new(self.x.rem_euclid(d), self.y.rem_euclid(d), self.z.rem_euclid(d))
+    }
+}
+
+impl Index<usize> for IVec3 {
+    type Output = i32;
+    fn index(&self, i: usize) -> &i32 {
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("IVec3 index {} out of range", i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_is_right_handed() {
+        assert_eq!(Vec3::X.cross(Vec3::Y), Vec3::Z);
+        assert_eq!(Vec3::Y.cross(Vec3::X), -Vec3::Z);
+    }
+
+    #[test]
+    fn normalize_and_length() {
+        let v = Vec3::new(3.0, 4.0, 0.0);
+        assert_eq!(v.length(), 5.0);
+        assert!(v.normalize().approx_eq(Vec3::new(0.6, 0.8, 0.0), 1e-6));
+        assert_eq!(Vec2::new(3.0, 4.0).length_squared(), 25.0);
+    }
+
+    #[test]
+    fn lerp_and_elementwise() {
+        let (a, b) = (Vec3::new(0.0, 2.0, -4.0), Vec3::new(2.0, 0.0, 4.0));
+        assert_eq!(a.lerp(b, 0.25), Vec3::new(0.5, 1.5, -2.0));
+        assert_eq!(a.min(b), Vec3::new(0.0, 0.0, -4.0));
+        assert_eq!(a.max(b), Vec3::new(2.0, 2.0, 4.0));
+        assert_eq!(Vec3::new(-0.5, 1.5, -2.0).floor(), Vec3::new(-1.0, 1.0, -2.0));
+    }
+
+    #[test]
+    fn block_and_chunk_coordinates_floor_towards_negative() {
+        let pos = IVec3::new(-1, 17, -16);
+        assert_eq!(pos.div_euclid(16), IVec3::new(-1, 1, -1));
+        assert_eq!(pos.rem_euclid(16), IVec3::new(15, 1, 0));
+        assert_eq!(Vec3::new(-0.25, 3.9, -7.0).floor().to_ivec3(), IVec3::new(-1, 3, -7));
+    }
+
+    #[test]
+    fn indexing() {
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!([v[0], v[1], v[2]], v.to_array());
+        assert_eq!(IVec3::new(4, 5, 6)[2], 6);
+    }
+}
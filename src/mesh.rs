@@ -0,0 +1,87 @@
+use crate::vertex_format;
+
+/// A single vertex as laid out in the vertex buffer. Matches `VertexIn` in `render.metal`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    /// Face shading multiplied into `color` by the fragment shader.
+    pub light: f32,
+    /// Ambient occlusion factor, also multiplied into `color`.
+    pub ao: f32,
+    /// Brightness from sky light, scaled by `Uniforms::sky_light` in the shader.
+    pub sky_light: f32,
+    /// Brightness from block light, tinted by `Uniforms::block_light`. The
+    /// brighter of the two light sources wins.
+    pub block_light: f32,
+}
+
+vertex_format!(Vertex {
+    0 => position: Float4,
+    1 => color: Float4,
+    2 => normal: Float3,
+    3 => uv: Float2,
+    4 => light: Float,
+    5 => ao: Float,
+    6 => sky_light: Float,
+    7 => block_light: Float,
+});
+
+impl Vertex {
+    /// A vertex in full daylight with no normal or texture coordinates.
+    pub const fn new(position: [f32; 4], color: [f32; 4]) -> Self {
+        Vertex {
+            position,
+            color,
+            normal: [0.0; 3],
+            uv: [0.0; 2],
+            light: 1.0,
+            ao: 1.0,
+            sky_light: 1.0,
+            block_light: 0.0,
+        }
+    }
+}
+
+/// Indexed triangle list living on the CPU, ready to be handed to `Renderer::upload_mesh`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        Mesh { vertices, indices }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Appends `other`, offsetting its indices.
+    pub fn append(&mut self, other: &Mesh) {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| i + base));
+    }
+
+    /// The red/green/blue demo triangle.
+    pub fn triangle() -> Self {
+        Mesh::new(
+            vec![
+                Vertex::new([0.0, 0.5, 0.0, 1.0], [1.0, 0.0, 0.0, 1.0]), // Top vertex (red)
+                Vertex::new([-0.5, -0.5, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]), // Bottom left vertex (green)
+                Vertex::new([0.5, -0.5, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]), // Bottom right vertex (blue)
+            ],
+            vec![0, 1, 2],
+        )
+    }
+}
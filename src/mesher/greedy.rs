@@ -0,0 +1,160 @@
+//! Greedy meshing.
+//!
+//! Each slice of the chunk perpendicular to a face direction is turned into a
+//! 2D mask of visible faces. The mask is then swept row by row: every
+//! unclaimed face grows as far as it can along u, then the whole row grows
+//! along v while the faces below it match, and the rectangle becomes a single
+//! quad. Faces only merge when their `FaceKey`s are equal, including the
+//! ambient occlusion and light levels of all four corners.
+
+use crate::block::{BlockRegistry, Face};
+use crate::math::IVec3;
+use crate::mesh::Mesh;
+use crate::world::{Chunk, CHUNK_SIZE, SECTION_COUNT};
+
+use super::{face_axes, push_quad, visible_face, BlockView, ChunkNeighbors};
+
+/// Y range covered by non-empty sections, or `None` for an all-air chunk.
+fn occupied_range(chunk: &Chunk) -> Option<(i32, i32)> {
+    let mut sections = (0..SECTION_COUNT).filter(|&index| !chunk.is_section_empty(index));
+    let first = sections.next()?;
+    let last = sections.next_back().unwrap_or(first);
+    let size = CHUNK_SIZE as i32;
+    Some((first as i32 * size, (last as i32 + 1) * size))
+}
+
+/// Builds the mesh of `chunk` like `mesh_chunk`, merging coplanar faces with
+/// the same texture, color, ambient occlusion and light into larger quads.
+pub fn mesh_chunk_greedy(chunk: &Chunk, neighbors: &ChunkNeighbors, registry: &BlockRegistry) -> Mesh {
+    let view = BlockView::new(chunk, neighbors);
+    let origin = chunk.pos().origin();
+    let origin = [origin.x, origin.y, origin.z];
+    let mut mesh = Mesh::default();
+
+    let Some((y_min, y_max)) = occupied_range(chunk) else {
+        return mesh;
+    };
+    let size = CHUNK_SIZE as i32;
+    let min = [0, y_min, 0];
+    let max = [size, y_max, size];
+    let mut mask = Vec::new();
+
+    for face in Face::ALL {
+        let (n, u, v) = face_axes(face);
+        let width = (max[u] - min[u]) as usize;
+        let height = (max[v] - min[v]) as usize;
+
+        for slice in min[n]..max[n] {
+            mask.clear();
+            for j in 0..height {
+                for i in 0..width {
+                    let mut pos = [0; 3];
+                    pos[n] = slice;
+                    pos[u] = min[u] + i as i32;
+                    pos[v] = min[v] + j as i32;
+                    mask.push(visible_face(&view, registry, IVec3::new(pos[0], pos[1], pos[2]), face));
+                }
+            }
+
+            for j in 0..height {
+                let mut i = 0;
+                while i < width {
+                    let Some(key) = mask[j * width + i] else {
+                        i += 1;
+                        continue;
+                    };
+
+                    let mut w = 1;
+                    while i + w < width && mask[j * width + i + w] == Some(key) {
+                        w += 1;
+                    }
+                    let mut h = 1;
+                    while j + h < height && mask[(j + h) * width + i..][..w].iter().all(|&f| f == Some(key)) {
+                        h += 1;
+                    }
+                    for row in j..j + h {
+                        mask[row * width + i..][..w].fill(None);
+                    }
+
+                    let mut corner = [0.0; 3];
+                    corner[n] = (origin[n] + slice) as f32;
+                    corner[u] = (origin[u] + min[u] + i as i32) as f32;
+                    corner[v] = (origin[v] + min[v] + j as i32) as f32;
+                    let mut extent = [1.0; 3];
+                    extent[u] = w as f32;
+                    extent[v] = h as f32;
+                    push_quad(&mut mesh, corner, extent, face, &key);
+
+                    i += w;
+                }
+            }
+        }
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::mesher::mesh_chunk;
+    use crate::world::{light_chunk, ChunkPos};
+    use crate::worldgen::{OverworldGenerator, TerrainGenerator};
+
+    /// A unit face: its normal, the bits of its block color and its minimum corner.
+    type UnitFace = ([i32; 3], [u32; 3], [i32; 3]);
+
+    /// Unit faces covered by the quads of `mesh`, with how many quads cover each.
+    fn covered_faces(mesh: &Mesh) -> HashMap<UnitFace, u32> {
+        let mut faces = HashMap::new();
+        for quad in mesh.vertices.chunks(4) {
+            let normal = quad[0].normal.map(|n| n as i32);
+            let color = [0, 1, 2].map(|i| quad[0].color[i].to_bits());
+            let min = [0, 1, 2].map(|axis| quad.iter().map(|v| v.position[axis] as i32).min().unwrap());
+            let max = [0, 1, 2].map(|axis| quad.iter().map(|v| v.position[axis] as i32).max().unwrap());
+            let span = |axis: usize| if normal[axis] != 0 { min[axis]..min[axis] + 1 } else { min[axis]..max[axis] };
+            for x in span(0) {
+                for y in span(1) {
+                    for z in span(2) {
+                        *faces.entry((normal, color, [x, y, z])).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        faces
+    }
+
+    #[test]
+    fn greedy_covers_exactly_the_naive_faces() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(11, &registry);
+        let pos = ChunkPos::new(-4, 9);
+        let mut chunk = generator.generate(pos);
+        // Some glass and torches so transparent blocks and block light are in the mix.
+        let glass = registry.expect_id("glass");
+        let torch = registry.expect_id("torch");
+        chunk.fill_box(IVec3::new(3, 90, 3), IVec3::new(9, 93, 12), glass);
+        chunk.set(6, 94, 6, torch);
+        light_chunk(&mut chunk, &registry);
+        let around: Vec<_> = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1)]
+            .into_iter()
+            .map(|(dx, dz)| generator.generate(pos.offset(dx, dz)))
+            .collect();
+        let neighbors = ChunkNeighbors::from_fn(pos, |p| around.iter().find(|c| c.pos() == p));
+
+        let naive = mesh_chunk(&chunk, &neighbors, &registry);
+        let greedy = mesh_chunk_greedy(&chunk, &neighbors, &registry);
+        assert!(greedy.vertices.len() < naive.vertices.len());
+
+        let naive_faces = covered_faces(&naive);
+        let greedy_faces = covered_faces(&greedy);
+        assert_eq!(naive_faces.len(), naive.vertices.len() / 4);
+        assert!(greedy_faces.values().all(|&n| n == 1), "greedy quads overlap");
+        let mut naive_keys: Vec<_> = naive_faces.into_keys().collect();
+        let mut greedy_keys: Vec<_> = greedy_faces.into_keys().collect();
+        naive_keys.sort();
+        greedy_keys.sort();
+        assert_eq!(naive_keys, greedy_keys);
+    }
+}
@@ -0,0 +1,608 @@
+//! Converts chunk voxel data into renderable meshes.
+//!
+//! Only faces between a block and a neighbour that does not hide it (air,
+//! or a transparent block of a different kind) are emitted. Output is
+//! deterministic: blocks are visited in y, z, x order and faces in
+//! `Face::ALL` order, so identical chunks always produce identical meshes.
+//!
+//! Two strategies are available, see `MeshingMode`: the naive mesher emits
+//! one quad per visible face, the greedy mesher (`greedy`) merges coplanar
+//! faces that look identical into larger rectangles.
+//!
+//! Sky and block light are sampled from the blocks in front of each face and
+//! smoothed per corner into the vertices' `sky_light` and `block_light`; the
+//! shader combines them with the time of day.
+
+mod greedy;
+mod tint;
+
+use std::collections::HashMap;
+
+use crate::block::{BlockDef, BlockId, BlockRegistry, Face, TextureId};
+use crate::math::IVec3;
+use crate::mesh::{Mesh, Vertex};
+use crate::world::{Biome, Chunk, ChunkPos, LightChannel, CHUNK_HEIGHT, CHUNK_SIZE, MAX_LIGHT};
+
+pub use greedy::mesh_chunk_greedy;
+use tint::ColumnTints;
+
+/// How a chunk is turned into quads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeshingMode {
+    /// One quad per visible face.
+    Naive,
+    /// Adjacent coplanar faces with the same appearance merged into rectangles.
+    #[default]
+    Greedy,
+}
+
+/// Meshes chunks with a global `MeshingMode` that individual chunks can override.
+#[derive(Clone, Debug, Default)]
+pub struct Mesher {
+    pub mode: MeshingMode,
+    overrides: HashMap<ChunkPos, MeshingMode>,
+}
+
+impl Mesher {
+    pub fn new(mode: MeshingMode) -> Self {
+        Mesher { mode, overrides: HashMap::new() }
+    }
+
+    /// Meshes the chunk at `pos` with `mode` regardless of the global mode;
+    /// `None` removes the override.
+    pub fn set_chunk_mode(&mut self, pos: ChunkPos, mode: Option<MeshingMode>) {
+        match mode {
+            Some(mode) => self.overrides.insert(pos, mode),
+            None => self.overrides.remove(&pos),
+        };
+    }
+
+    pub fn mode_for(&self, pos: ChunkPos) -> MeshingMode {
+        self.overrides.get(&pos).copied().unwrap_or(self.mode)
+    }
+
+    pub fn mesh(&self, chunk: &Chunk, neighbors: &ChunkNeighbors, registry: &BlockRegistry) -> Mesh {
+        match self.mode_for(chunk.pos()) {
+            MeshingMode::Naive => mesh_chunk(chunk, neighbors, registry),
+            MeshingMode::Greedy => mesh_chunk_greedy(chunk, neighbors, registry),
+        }
+    }
+}
+
+/// The horizontally adjacent chunks, used to cull faces on the chunk border
+/// and to compute ambient occlusion at its edges and corners. Columns span
+/// the full world height, so there are no vertical neighbours: above the
+/// chunk is air and below it nothing is ever visible. A missing neighbour
+/// counts as air.
+#[derive(Clone, Copy, Default)]
+pub struct ChunkNeighbors<'a> {
+    /// +X
+    pub east: Option<&'a Chunk>,
+    /// -X
+    pub west: Option<&'a Chunk>,
+    /// +Z
+    pub south: Option<&'a Chunk>,
+    /// -Z
+    pub north: Option<&'a Chunk>,
+    /// +X -Z
+    pub north_east: Option<&'a Chunk>,
+    /// -X -Z
+    pub north_west: Option<&'a Chunk>,
+    /// +X +Z
+    pub south_east: Option<&'a Chunk>,
+    /// -X +Z
+    pub south_west: Option<&'a Chunk>,
+}
+
+impl<'a> ChunkNeighbors<'a> {
+    /// Looks up all eight neighbours of the chunk at `pos`.
+    pub fn from_fn(pos: ChunkPos, mut get: impl FnMut(ChunkPos) -> Option<&'a Chunk>) -> Self {
+        ChunkNeighbors {
+            east: get(pos.offset(1, 0)),
+            west: get(pos.offset(-1, 0)),
+            south: get(pos.offset(0, 1)),
+            north: get(pos.offset(0, -1)),
+            north_east: get(pos.offset(1, -1)),
+            north_west: get(pos.offset(-1, -1)),
+            south_east: get(pos.offset(1, 1)),
+            south_west: get(pos.offset(-1, 1)),
+        }
+    }
+}
+
+/// Read access to a chunk plus the border of its neighbours.
+struct BlockView<'a> {
+    chunk: &'a Chunk,
+    neighbors: &'a ChunkNeighbors<'a>,
+    tints: ColumnTints,
+}
+
+/// What lies outside the bottom of the world.
+enum Outside {
+    Air,
+    Opaque,
+}
+
+impl<'a> BlockView<'a> {
+    fn new(chunk: &'a Chunk, neighbors: &'a ChunkNeighbors<'a>) -> Self {
+        let mut view = BlockView { chunk, neighbors, tints: ColumnTints::default() };
+        view.tints = ColumnTints::new(&view);
+        view
+    }
+
+    /// Block at local coordinates that may be up to one block outside the chunk.
+    fn get(&self, x: i32, y: i32, z: i32) -> Result<BlockId, Outside> {
+        if y < 0 {
+            return Err(Outside::Opaque);
+        }
+        if y >= CHUNK_HEIGHT as i32 {
+            return Err(Outside::Air);
+        }
+        self.column(x, z)
+            .map(|(chunk, x, z)| chunk.get(x, y as usize, z))
+            .ok_or(Outside::Air)
+    }
+
+    /// Biome of the column at local coordinates that may be up to one chunk
+    /// outside the chunk, or `None` if that neighbour is missing.
+    fn biome(&self, x: i32, z: i32) -> Option<Biome> {
+        self.column(x, z).map(|(chunk, x, z)| chunk.biome(x, z))
+    }
+
+    /// The chunk holding local column `(x, z)` and the column's coordinates in it.
+    fn column(&self, x: i32, z: i32) -> Option<(&'a Chunk, usize, usize)> {
+        let size = CHUNK_SIZE as i32;
+        let chunk = match (x.div_euclid(size), z.div_euclid(size)) {
+            (0, 0) => Some(self.chunk),
+            (1, 0) => self.neighbors.east,
+            (-1, 0) => self.neighbors.west,
+            (0, 1) => self.neighbors.south,
+            (0, -1) => self.neighbors.north,
+            (1, -1) => self.neighbors.north_east,
+            (-1, -1) => self.neighbors.north_west,
+            (1, 1) => self.neighbors.south_east,
+            (-1, 1) => self.neighbors.south_west,
+            _ => None,
+        }?;
+        Some((chunk, x.rem_euclid(size) as usize, z.rem_euclid(size) as usize))
+    }
+
+    /// Sky and block light at local coordinates that may be up to one block
+    /// outside the chunk. Like a missing neighbour's blocks, its light
+    /// counts as open sky.
+    fn light(&self, pos: IVec3) -> [u8; 2] {
+        if pos.y < 0 {
+            return [0, 0];
+        }
+        if pos.y >= CHUNK_HEIGHT as i32 {
+            return [MAX_LIGHT, 0];
+        }
+        self.column(pos.x, pos.z).map_or([MAX_LIGHT, 0], |(chunk, x, z)| {
+            let y = pos.y as usize;
+            [chunk.light(x, y, z, LightChannel::Sky), chunk.light(x, y, z, LightChannel::Block)]
+        })
+    }
+
+    /// Whether the block at local coordinates casts ambient occlusion.
+    fn occludes(&self, registry: &BlockRegistry, pos: IVec3) -> bool {
+        match self.get(pos.x, pos.y, pos.z) {
+            Ok(id) => registry.def(id).is_opaque(),
+            Err(Outside::Air) => false,
+            Err(Outside::Opaque) => true,
+        }
+    }
+}
+
+/// Whether the `face` of `block` is visible next to `neighbor`.
+fn face_visible(block: &BlockDef, neighbor: Result<&BlockDef, Outside>) -> bool {
+    match neighbor {
+        Ok(neighbor) if neighbor.is_opaque() => false,
+        // Faces between two blocks of the same transparent kind (water, glass) are hidden.
+        Ok(neighbor) => !(block.transparent && neighbor.id == block.id),
+        Err(Outside::Air) => true,
+        Err(Outside::Opaque) => false,
+    }
+}
+
+/// Flat per-face shading so faces stay distinguishable under even light.
+pub fn face_shade(face: Face) -> f32 {
+    match face {
+        Face::Up => 1.0,
+        Face::Down => 0.5,
+        Face::East | Face::West => 0.8,
+        Face::South | Face::North => 0.65,
+    }
+}
+
+/// Brightness of a vertex for each ambient occlusion level, from fully
+/// occluded (0) to open (3).
+pub const AO_BRIGHTNESS: [f32; 4] = [0.45, 0.65, 0.82, 1.0];
+
+/// Ambient occlusion level of a face vertex, from the two blocks beside it
+/// and the one diagonal to it in the layer in front of the face: 3 when none
+/// of them is solid, down to 0. Two solid sides hide the corner block, so
+/// they occlude fully whatever it is.
+pub fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u8 {
+    if side1 && side2 {
+        0
+    } else {
+        3 - (side1 as u8 + side2 as u8 + corner as u8)
+    }
+}
+
+/// Fraction of full brightness at a light level (0.0..=15.0). Each level
+/// down is 20% darker, over a faint ambient floor so unlit caves are not
+/// pitch black.
+pub fn light_brightness(level: f32) -> f32 {
+    const AMBIENT: f32 = 0.04;
+    AMBIENT + (1.0 - AMBIENT) * 0.8f32.powf(MAX_LIGHT as f32 - level)
+}
+
+/// Axes (0 = X, 1 = Y, 2 = Z) of a face: its normal, then the u and v axes
+/// spanning its plane.
+fn face_axes(face: Face) -> (usize, usize, usize) {
+    match face {
+        Face::East | Face::West => (0, 2, 1),
+        Face::Up | Face::Down => (1, 0, 2),
+        Face::South | Face::North => (2, 0, 1),
+    }
+}
+
+/// Steps from the block in front of `face` towards each of its corners,
+/// along the face's u and v axes, in `face_corners` order.
+fn corner_steps(face: Face) -> [(IVec3, IVec3); 4] {
+    let (_, u, v) = face_axes(face);
+    face_corners(face).map(|corner| {
+        let step = |axis: usize| {
+            let mut offset = [0; 3];
+            offset[axis] = if corner[axis] > 0.5 { 1 } else { -1 };
+            IVec3::new(offset[0], offset[1], offset[2])
+        };
+        (step(u), step(v))
+    })
+}
+
+/// Ambient occlusion level of each corner of `face` of the block at local
+/// `pos`, in `face_corners` order.
+fn face_ao(view: &BlockView, registry: &BlockRegistry, pos: IVec3, face: Face) -> [u8; 4] {
+    let front = pos + face.normal();
+    corner_steps(face).map(|(du, dv)| {
+        vertex_ao(
+            view.occludes(registry, front + du),
+            view.occludes(registry, front + dv),
+            view.occludes(registry, front + du + dv),
+        )
+    })
+}
+
+/// Sky and block light of each corner of `face` of the block at local `pos`,
+/// in quarter levels (0..=60): the average over the four blocks in front of
+/// the face that touch the corner, leaving out opaque ones, and the diagonal
+/// one when both sides are opaque.
+fn face_light(view: &BlockView, registry: &BlockRegistry, pos: IVec3, face: Face) -> [[u8; 2]; 4] {
+    let front = pos + face.normal();
+    corner_steps(face).map(|(du, dv)| {
+        let (side1, side2) = (view.occludes(registry, front + du), view.occludes(registry, front + dv));
+        let samples = [
+            (front, true),
+            (front + du, !side1),
+            (front + dv, !side2),
+            (front + du + dv, !(side1 && side2 || view.occludes(registry, front + du + dv))),
+        ];
+        let (mut sum, mut count) = ([0u32; 2], 0u32);
+        for (pos, _) in samples.into_iter().filter(|&(_, open)| open) {
+            let [sky, block] = view.light(pos);
+            sum[0] += sky as u32;
+            sum[1] += block as u32;
+            count += 1;
+        }
+        sum.map(|sum| ((sum * 4 + count / 2) / count) as u8)
+    })
+}
+
+/// Corners of each face of the unit cube, counter-clockwise seen from outside.
+fn face_corners(face: Face) -> [[f32; 3]; 4] {
+    match face {
+        Face::East => [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [1.0, 0.0, 1.0]],
+        Face::West => [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]],
+        Face::Up => [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0]],
+        Face::Down => [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]],
+        Face::South => [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]],
+        Face::North => [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]],
+    }
+}
+
+/// Texture coordinates of a corner of a quad `height` blocks tall: u runs
+/// along X (Z for east/west faces), v along Z on top/bottom faces and
+/// downwards on side faces. Merged quads span several texture repeats.
+fn corner_uv(face: Face, corner: [f32; 3], height: f32) -> [f32; 2] {
+    let [x, y, z] = corner;
+    match face {
+        Face::Up | Face::Down => [x, z],
+        Face::East | Face::West => [z, height - y],
+        Face::South | Face::North => [x, height - y],
+    }
+}
+
+/// Everything that decides what a face looks like. Faces are only merged
+/// when their keys are equal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FaceKey {
+    texture: TextureId,
+    color: [f32; 3],
+    shade: f32,
+    /// Per-corner ambient occlusion levels, in `face_corners` order.
+    ao: [u8; 4],
+    /// Per-corner sky and block light, in quarter levels, as from `face_light`.
+    light: [[u8; 2]; 4],
+}
+
+/// The key of the `face` of the block at local `pos`, if that face is visible.
+fn visible_face(view: &BlockView, registry: &BlockRegistry, pos: IVec3, face: Face) -> Option<FaceKey> {
+    let block = view.get(pos.x, pos.y, pos.z).ok().filter(|block| !block.is_air())?;
+    let def = registry.def(block);
+    let n = pos + face.normal();
+    let neighbor = view.get(n.x, n.y, n.z).map(|id| registry.def(id));
+    face_visible(def, neighbor).then(|| FaceKey {
+        texture: def.texture(face),
+        color: view.tints.color(def, pos.x, pos.z),
+        shade: face_shade(face),
+        ao: face_ao(view, registry, pos, face),
+        light: face_light(view, registry, pos, face),
+    })
+}
+
+/// Appends a quad on `face` of the box spanning `origin..origin + size`
+/// (world block coordinates) as two triangles.
+fn push_quad(mesh: &mut Mesh, origin: [f32; 3], size: [f32; 3], face: Face, key: &FaceKey) {
+    let normal = face.normal();
+    let normal = [normal.x as f32, normal.y as f32, normal.z as f32];
+    let base = mesh.vertices.len() as u32;
+    for ((corner, ao), [sky, block]) in face_corners(face).into_iter().zip(key.ao).zip(key.light) {
+        let corner = [corner[0] * size[0], corner[1] * size[1], corner[2] * size[2]];
+        let [r, g, b] = key.color;
+        mesh.vertices.push(Vertex {
+            position: [origin[0] + corner[0], origin[1] + corner[1], origin[2] + corner[2], 1.0],
+            color: [r, g, b, 1.0],
+            normal,
+            uv: corner_uv(face, corner, size[1]),
+            light: key.shade,
+            ao: AO_BRIGHTNESS[ao as usize],
+            sky_light: light_brightness(sky as f32 / 4.0),
+            block_light: light_brightness(block as f32 / 4.0),
+        });
+    }
+    // Split along the brighter diagonal so occlusion interpolates symmetrically.
+    let [a0, a1, a2, a3] = key.ao;
+    if a1 + a3 > a0 + a2 {
+        mesh.indices.extend_from_slice(&[base + 1, base + 2, base + 3, base + 1, base + 3, base]);
+    } else {
+        mesh.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+/// Builds the mesh of every visible block face in `chunk`, in world
+/// coordinates, one quad per face.
+pub fn mesh_chunk(chunk: &Chunk, neighbors: &ChunkNeighbors, registry: &BlockRegistry) -> Mesh {
+    let view = BlockView::new(chunk, neighbors);
+    let origin = chunk.pos().origin();
+    let mut mesh = Mesh::default();
+
+    for (pos, _) in chunk.iter_non_air() {
+        for face in Face::ALL {
+            if let Some(key) = visible_face(&view, registry, pos, face) {
+                let world = origin + pos;
+                let origin = [world.x as f32, world.y as f32, world.z as f32];
+                push_quad(&mut mesh, origin, [1.0; 3], face, &key);
+            }
+        }
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::light_chunk;
+    use crate::worldgen::{OverworldGenerator, TerrainGenerator};
+
+    pub(super) fn chunk_with(pos: ChunkPos, blocks: &[(IVec3, BlockId)]) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        for &(p, block) in blocks {
+            chunk.set(p.x as usize, p.y as usize, p.z as usize, block);
+        }
+        chunk
+    }
+
+    fn quad_count(mesh: &Mesh) -> usize {
+        assert_eq!(mesh.vertices.len() % 4, 0);
+        assert_eq!(mesh.indices.len(), mesh.vertices.len() / 4 * 6);
+        mesh.vertices.len() / 4
+    }
+
+    /// Quads whose normal points along `normal`, by their first corner.
+    fn quads_facing(mesh: &Mesh, normal: [f32; 3]) -> Vec<[f32; 3]> {
+        mesh.vertices
+            .chunks(4)
+            .filter(|quad| quad[0].normal == normal)
+            .map(|quad| [quad[0].position[0], quad[0].position[1], quad[0].position[2]])
+            .collect()
+    }
+
+    fn mesh_alone(registry: &BlockRegistry, blocks: &[(IVec3, &str)]) -> Mesh {
+        let blocks: Vec<_> = blocks.iter().map(|&(p, name)| (p, registry.expect_id(name))).collect();
+        let chunk = chunk_with(ChunkPos::new(0, 0), &blocks);
+        mesh_chunk(&chunk, &ChunkNeighbors::default(), registry)
+    }
+
+    #[test]
+    fn single_block_has_six_faces() {
+        let registry = BlockRegistry::builtin();
+        let mesh = mesh_alone(&registry, &[(IVec3::new(4, 64, 4), "stone")]);
+        assert_eq!(quad_count(&mesh), 6);
+        for face in Face::ALL {
+            let n = face.normal();
+            assert_eq!(quads_facing(&mesh, [n.x as f32, n.y as f32, n.z as f32]).len(), 1, "{:?}", face);
+        }
+    }
+
+    #[test]
+    fn shared_face_between_two_blocks_is_culled() {
+        let registry = BlockRegistry::builtin();
+        let mesh = mesh_alone(&registry, &[(IVec3::new(4, 64, 4), "stone"), (IVec3::new(5, 64, 4), "dirt")]);
+        assert_eq!(quad_count(&mesh), 10);
+        assert_eq!(quads_facing(&mesh, [1.0, 0.0, 0.0]), [[6.0, 64.0, 4.0]]);
+        assert_eq!(quads_facing(&mesh, [-1.0, 0.0, 0.0]), [[4.0, 64.0, 4.0]]);
+    }
+
+    #[test]
+    fn bottom_of_the_world_is_never_visible() {
+        let registry = BlockRegistry::builtin();
+        let mesh = mesh_alone(&registry, &[(IVec3::new(4, 0, 4), "bedrock")]);
+        assert_eq!(quad_count(&mesh), 5);
+        assert!(quads_facing(&mesh, [0.0, -1.0, 0.0]).is_empty());
+    }
+
+    #[test]
+    fn stone_shows_through_glass() {
+        let registry = BlockRegistry::builtin();
+        let mesh = mesh_alone(&registry, &[(IVec3::new(4, 64, 4), "stone"), (IVec3::new(5, 64, 4), "glass")]);
+        // Stone keeps its face behind the glass; the glass face against the
+        // stone is hidden.
+        assert_eq!(quad_count(&mesh), 11);
+        assert_eq!(quads_facing(&mesh, [1.0, 0.0, 0.0]), [[5.0, 64.0, 4.0], [6.0, 64.0, 4.0]]);
+        assert_eq!(quads_facing(&mesh, [-1.0, 0.0, 0.0]), [[4.0, 64.0, 4.0]]);
+    }
+
+    #[test]
+    fn same_transparent_kind_hides_shared_faces() {
+        let registry = BlockRegistry::builtin();
+        for name in ["glass", "water"] {
+            let mesh = mesh_alone(&registry, &[(IVec3::new(4, 64, 4), name), (IVec3::new(4, 65, 4), name)]);
+            assert_eq!(quad_count(&mesh), 10, "{}", name);
+        }
+        // Different transparent kinds show each other.
+        let mesh = mesh_alone(&registry, &[(IVec3::new(4, 64, 4), "glass"), (IVec3::new(4, 65, 4), "water")]);
+        assert_eq!(quad_count(&mesh), 12);
+    }
+
+    #[test]
+    fn faces_on_the_chunk_border() {
+        let registry = BlockRegistry::builtin();
+        let stone = registry.expect_id("stone");
+        let chunk = chunk_with(ChunkPos::new(0, 0), &[(IVec3::new(15, 64, 0), stone)]);
+
+        // Missing neighbours count as air.
+        let alone = mesh_chunk(&chunk, &ChunkNeighbors::default(), &registry);
+        assert_eq!(quad_count(&alone), 6);
+
+        let empty = Chunk::new(ChunkPos::new(1, 0));
+        let neighbors = ChunkNeighbors { east: Some(&empty), ..Default::default() };
+        assert_eq!(quad_count(&mesh_chunk(&chunk, &neighbors, &registry)), 6);
+
+        let east = chunk_with(ChunkPos::new(1, 0), &[(IVec3::new(0, 64, 0), stone)]);
+        let north = chunk_with(ChunkPos::new(0, -1), &[(IVec3::new(15, 64, 15), stone)]);
+        let neighbors = ChunkNeighbors { east: Some(&east), north: Some(&north), ..Default::default() };
+        let mesh = mesh_chunk(&chunk, &neighbors, &registry);
+        assert_eq!(quad_count(&mesh), 4);
+        assert!(quads_facing(&mesh, [1.0, 0.0, 0.0]).is_empty());
+        assert!(quads_facing(&mesh, [0.0, 0.0, -1.0]).is_empty());
+    }
+
+    #[test]
+    fn meshing_is_deterministic() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(7, &registry);
+        let pos = ChunkPos::new(2, -3);
+        let mut chunk = generator.generate(pos);
+        light_chunk(&mut chunk, &registry);
+        let neighbors_owned: Vec<_> = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .map(|(dx, dz)| generator.generate(pos.offset(dx, dz)))
+            .collect();
+        let neighbors = ChunkNeighbors::from_fn(pos, |p| neighbors_owned.iter().find(|c| c.pos() == p));
+
+        let bytes = |mesh: &Mesh| -> Vec<u32> {
+            let mut bits = Vec::new();
+            for v in &mesh.vertices {
+                let fields = v.position.iter().chain(&v.color).chain(&v.normal).chain(&v.uv);
+                bits.extend(fields.chain([&v.light, &v.ao, &v.sky_light, &v.block_light]).map(|f| f.to_bits()));
+            }
+            bits.extend(&mesh.indices);
+            bits
+        };
+        let first = mesh_chunk(&chunk, &neighbors, &registry);
+        let second = mesh_chunk(&chunk.clone(), &neighbors, &registry);
+        assert!(quad_count(&first) > 100);
+        assert_eq!(bytes(&first), bytes(&second));
+    }
+
+    #[test]
+    fn vertex_ao_levels() {
+        assert_eq!(vertex_ao(false, false, false), 3);
+        assert_eq!(vertex_ao(false, false, true), 2);
+        assert_eq!(vertex_ao(true, false, false), 2);
+        assert_eq!(vertex_ao(false, true, true), 1);
+        // Two sides occlude fully whether or not the corner block is there.
+        assert_eq!(vertex_ao(true, true, false), 0);
+        assert_eq!(vertex_ao(true, true, true), 0);
+    }
+
+    /// Per-corner AO of the top face of a stone block at (4, 64, 4) with
+    /// stone at `around`, and the quad's indices relative to its first vertex.
+    fn top_face_ao(around: &[IVec3]) -> ([u8; 4], Vec<u32>) {
+        let registry = BlockRegistry::builtin();
+        let stone = registry.expect_id("stone");
+        let pos = IVec3::new(4, 64, 4);
+        let blocks: Vec<_> = around.iter().chain([&pos]).map(|&p| (p, stone)).collect();
+        let chunk = chunk_with(ChunkPos::new(0, 0), &blocks);
+        let neighbors = ChunkNeighbors::default();
+        let view = BlockView::new(&chunk, &neighbors);
+        let ao = face_ao(&view, &registry, pos, Face::Up);
+
+        let mesh = mesh_chunk(&chunk, &neighbors, &registry);
+        let quad = mesh
+            .vertices
+            .chunks(4)
+            .position(|q| q[0].position == [4.0, 65.0, 4.0, 1.0] && q[0].normal == [0.0, 1.0, 0.0])
+            .expect("top face is visible");
+        let base = quad as u32 * 4;
+        let vertices = &mesh.vertices[quad * 4..][..4];
+        assert_eq!(vertices.iter().map(|v| v.ao).collect::<Vec<_>>(), ao.map(|ao| AO_BRIGHTNESS[ao as usize]));
+        (ao, mesh.indices[quad * 6..][..6].iter().map(|i| i - base).collect())
+    }
+
+    // Up face corners in order: (-x, -z), (-x, +z), (+x, +z), (+x, -z).
+    const REGULAR: [u32; 6] = [0, 1, 2, 0, 2, 3];
+    const FLIPPED: [u32; 6] = [1, 2, 3, 1, 3, 0];
+
+    #[test]
+    fn lone_block_is_unoccluded() {
+        assert_eq!(top_face_ao(&[]), ([3; 4], REGULAR.to_vec()));
+    }
+
+    #[test]
+    fn inner_corner_occludes_fully() {
+        let (ao, indices) = top_face_ao(&[IVec3::new(3, 65, 4), IVec3::new(4, 65, 3)]);
+        assert_eq!(ao, [0, 2, 3, 2]);
+        assert_eq!(indices, FLIPPED);
+    }
+
+    #[test]
+    fn diagonal_neighbor_only() {
+        let (ao, indices) = top_face_ao(&[IVec3::new(3, 65, 3)]);
+        assert_eq!(ao, [2, 3, 3, 3]);
+        // The darker corner 0 must not sit on the shared diagonal.
+        assert_eq!(indices, FLIPPED);
+
+        let (ao, indices) = top_face_ao(&[IVec3::new(3, 65, 5)]);
+        assert_eq!(ao, [3, 2, 3, 3]);
+        assert_eq!(indices, REGULAR);
+    }
+
+    #[test]
+    fn blocks_below_the_face_do_not_occlude() {
+        let (ao, _) = top_face_ao(&[IVec3::new(3, 64, 4), IVec3::new(4, 64, 3), IVec3::new(3, 64, 3)]);
+        assert_eq!(ao, [3; 4]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Biome tint blending.
+//!
+//! Tinted blocks take their color from the biome of their column, averaged
+//! over the surrounding columns so colors fade across biome borders instead
+//! of changing from one block to the next.
+
+use crate::block::BlockDef;
+use crate::world::{Tint, CHUNK_SIZE};
+
+use super::BlockView;
+
+/// Columns on each side averaged into a column's tint.
+const BLEND_RADIUS: i32 = 2;
+
+/// Blended grass, foliage and water colors of every column of a chunk,
+/// indexed by `z * CHUNK_SIZE + x`.
+#[derive(Default)]
+pub(super) struct ColumnTints {
+    colors: Vec<[[f32; 3]; 3]>,
+}
+
+impl ColumnTints {
+    pub(super) fn new(view: &BlockView) -> Self {
+        let size = CHUNK_SIZE as i32;
+        let mut colors = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+        for z in 0..size {
+            for x in 0..size {
+                let mut sum = [[0.0; 3]; 3];
+                let mut count = 0.0;
+                for dz in -BLEND_RADIUS..=BLEND_RADIUS {
+                    for dx in -BLEND_RADIUS..=BLEND_RADIUS {
+                        // Missing neighbours are left out of the average.
+                        let Some(biome) = view.biome(x + dx, z + dz) else { continue };
+                        let def = biome.def();
+                        for (sum, color) in sum.iter_mut().zip([def.grass_tint, def.foliage_tint, def.water_tint]) {
+                            for (sum, channel) in sum.iter_mut().zip(color) {
+                                *sum += channel;
+                            }
+                        }
+                        count += 1.0;
+                    }
+                }
+                colors.push(sum.map(|color| color.map(|channel| channel / count)));
+            }
+        }
+        ColumnTints { colors }
+    }
+
+    /// Color of `block` in local column `(x, z)`.
+    pub(super) fn color(&self, block: &BlockDef, x: i32, z: i32) -> [f32; 3] {
+        let index = match block.tint {
+            Tint::None => return block.color,
+            Tint::Grass => 0,
+            Tint::Foliage => 1,
+            Tint::Water => 2,
+        };
+        self.colors[z as usize * CHUNK_SIZE + x as usize][index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::BlockRegistry;
+    use crate::mesher::ChunkNeighbors;
+    use crate::world::{Biome, Chunk, ChunkPos};
+
+    use super::*;
+
+    /// Grass tints of row `z = 0` of a chunk that is plains west of x = 8 and desert from there on.
+    fn two_biome_row() -> Vec<[f32; 3]> {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.set_biome(x, z, if x < 8 { Biome::Plains } else { Biome::Desert });
+            }
+        }
+        let neighbors = ChunkNeighbors::default();
+        let view = BlockView::new(&chunk, &neighbors);
+        let registry = BlockRegistry::builtin();
+        let grass = registry.def(registry.expect_id("grass"));
+        assert_eq!(grass.tint, Tint::Grass);
+        (0..CHUNK_SIZE as i32).map(|x| view.tints.color(grass, x, 0)).collect()
+    }
+
+    fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+        [0, 1, 2].map(|i| a[i] * (1.0 - t) + b[i] * t)
+    }
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+        assert!(actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-5), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn colors_fade_across_a_biome_border() {
+        let plains = Biome::Plains.def().grass_tint;
+        let desert = Biome::Desert.def().grass_tint;
+        let row = two_biome_row();
+        // Far from the border, and at the chunk edge where missing neighbours are left out.
+        assert_close(row[0], plains);
+        assert_close(row[5], plains);
+        assert_close(row[10], desert);
+        assert_close(row[15], desert);
+        // Averaged over the five columns x - 2..=x + 2.
+        assert_close(row[6], mix(plains, desert, 0.2));
+        assert_close(row[7], mix(plains, desert, 0.4));
+        assert_close(row[8], mix(plains, desert, 0.6));
+        assert_close(row[9], mix(plains, desert, 0.8));
+    }
+
+    #[test]
+    fn untinted_blocks_keep_their_color() {
+        let registry = BlockRegistry::builtin();
+        let stone = registry.def(registry.expect_id("stone"));
+        assert_eq!(stone.tint, Tint::None);
+        let chunk = Chunk::new(ChunkPos::new(0, 0));
+        let neighbors = ChunkNeighbors::default();
+        let view = BlockView::new(&chunk, &neighbors);
+        assert_eq!(view.tints.color(stone, 3, 4), stone.color);
+    }
+}
@@ -0,0 +1,535 @@
+//! Movement of entities through the block grid.
+//!
+//! A `Body` is an axis-aligned box that falls, walks and swims through a
+//! `Terrain`. Each step sweeps it along Y, then X, then Z, stopping it flush
+//! against the first block box in the way, so nothing tunnels through walls
+//! however fast it moves. Everything is plain `f32` arithmetic in a fixed
+//! order, so the same steps from the same state always end in the same
+//! place.
+
+use crate::block::BlockDef;
+use crate::math::{IVec3, Vec3};
+use crate::world::{World, CHUNK_HEIGHT};
+
+/// Downward acceleration in blocks per second squared.
+pub const GRAVITY: f32 = 32.0;
+/// Fastest a body falls through air.
+pub const TERMINAL_VELOCITY: f32 = 78.4;
+/// Upward speed at the start of a jump, lifting the feet about 1.25 blocks.
+pub const JUMP_SPEED: f32 = 9.2;
+/// Highest ledge a body on the ground walks up without jumping.
+pub const STEP_HEIGHT: f32 = 0.6;
+
+/// How quickly horizontal velocity approaches the wanted one, per second,
+/// on ground of friction 1.0, in the air and in liquid.
+const GROUND_ACCELERATION: f32 = 12.0;
+const AIR_ACCELERATION: f32 = 2.0;
+const LIQUID_ACCELERATION: f32 = 4.0;
+
+/// Liquid slows walking to this fraction, pulls down with this fraction of
+/// gravity and lets bodies sink and swim at these speeds.
+const LIQUID_SPEED_FACTOR: f32 = 0.5;
+const LIQUID_GRAVITY_FACTOR: f32 = 0.25;
+const SINK_SPEED: f32 = 2.0;
+const SWIM_SPEED: f32 = 3.0;
+/// Speed up and down a ladder.
+const CLIMB_SPEED: f32 = 2.35;
+
+/// Gap below which a box counts as touching a block face.
+const CONTACT: f32 = 1e-4;
+
+/// An axis-aligned box in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    pub fn offset(self, by: Vec3) -> Self {
+        Aabb { min: self.min + by, max: self.max + by }
+    }
+
+    /// Block positions whose cells overlap the box.
+    fn cells(&self) -> impl Iterator<Item = IVec3> {
+        let min = self.min.floor().to_ivec3();
+        let max = self.max.floor().to_ivec3();
+        (min.y..=max.y).flat_map(move |y| {
+            (min.z..=max.z).flat_map(move |z| (min.x..=max.x).map(move |x| IVec3::new(x, y, z)))
+        })
+    }
+}
+
+/// How a block affects bodies moving through it.
+///
+/// Friction decides how far a body slides once it stops walking:
+///
+/// ```
+/// use metalcraft::block::BlockRegistry;
+/// use metalcraft::math::{IVec3, Vec3};
+/// use metalcraft::physics::{Body, BlockPhysics, Intent};
+///
+/// let registry = BlockRegistry::builtin();
+/// let slide = |name: &str| {
+///     let ground = BlockPhysics::of(registry.def(registry.expect_id(name)));
+///     let terrain = |pos: IVec3| if pos.y < 64 { ground } else { BlockPhysics::AIR };
+///     let mut body = Body::new(Vec3::new(0.5, 64.0, 0.5), 0.3, 1.8);
+///     let walk = Intent { walk: Vec3::new(4.3, 0.0, 0.0), ..Intent::default() };
+///     (0..60).for_each(|_| body.step(&terrain, &walk, 1.0 / 60.0));
+///     let stop = body.position.x;
+///     (0..120).for_each(|_| body.step(&terrain, &Intent::default(), 1.0 / 60.0));
+///     body.position.x - stop
+/// };
+/// assert!(slide("stone") < 0.5 && slide("ice") > 2.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockPhysics {
+    /// Top of the collision box above the bottom of the block; 0 lets
+    /// bodies pass through.
+    pub height: f32,
+    pub friction: f32,
+    pub climbable: bool,
+    pub liquid: bool,
+}
+
+impl BlockPhysics {
+    pub const AIR: BlockPhysics = BlockPhysics { height: 0.0, friction: 1.0, climbable: false, liquid: false };
+    pub const SOLID: BlockPhysics = BlockPhysics { height: 1.0, friction: 1.0, climbable: false, liquid: false };
+
+    pub fn of(def: &BlockDef) -> Self {
+        BlockPhysics {
+            height: def.collision_height,
+            friction: def.friction,
+            climbable: def.climbable,
+            liquid: def.liquid,
+        }
+    }
+
+    /// The collision box of a block with these physics at `pos`.
+    fn collision_box(&self, pos: IVec3) -> Option<Aabb> {
+        let min = pos.to_vec3();
+        (self.height > 0.0).then(|| Aabb::new(min, min + Vec3::new(1.0, self.height, 1.0)))
+    }
+}
+
+/// Blocks as far as physics is concerned. Implemented by `World` and by any
+/// `Fn(IVec3) -> BlockPhysics`, which makes up terrain on the spot.
+pub trait Terrain {
+    fn physics(&self, pos: IVec3) -> BlockPhysics;
+}
+
+impl<F: Fn(IVec3) -> BlockPhysics> Terrain for F {
+    fn physics(&self, pos: IVec3) -> BlockPhysics {
+        self(pos)
+    }
+}
+
+/// Above and below the world is empty; chunks that are not generated yet
+/// are solid, so nothing wanders into them.
+impl Terrain for World {
+    fn physics(&self, pos: IVec3) -> BlockPhysics {
+        match self.block(pos) {
+            Some(block) => BlockPhysics::of(self.registry().def(block)),
+            None if pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 => BlockPhysics::AIR,
+            None => BlockPhysics::SOLID,
+        }
+    }
+}
+
+/// What a body tries to do during a step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Intent {
+    /// Horizontal velocity to reach, in blocks per second; `y` is ignored.
+    pub walk: Vec3,
+    /// Jump off the ground, swim up in liquid or climb up a ladder.
+    pub jump: bool,
+    /// Hold still on a ladder instead of sliding down or climbing.
+    pub sneak: bool,
+}
+
+/// An entity's collision box and motion. `position` is the center of the
+/// bottom face.
+///
+/// A player dropped from y = 80 onto ground whose top is at y = 64 lands
+/// exactly on it:
+///
+/// ```
+/// use metalcraft::math::{IVec3, Vec3};
+/// use metalcraft::physics::{Body, BlockPhysics, Intent, TERMINAL_VELOCITY};
+///
+/// let ground = |pos: IVec3| if pos.y < 64 { BlockPhysics::SOLID } else { BlockPhysics::AIR };
+/// let mut body = Body::new(Vec3::new(0.5, 80.0, 0.5), 0.3, 1.8);
+/// for _ in 0..120 {
+///     body.step(&ground, &Intent::default(), 1.0 / 60.0);
+///     assert!(body.velocity.y >= -TERMINAL_VELOCITY);
+/// }
+/// assert_eq!(body.position, Vec3::new(0.5, 64.0, 0.5));
+/// assert!(body.on_ground && body.velocity.y == 0.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Half the width of the box on X and Z.
+    pub half_width: f32,
+    pub height: f32,
+    /// Standing on something after the last step.
+    pub on_ground: bool,
+    /// Stopped by a wall on X or Z during the last step.
+    pub against_wall: bool,
+    /// Overlapping a liquid during the last step.
+    pub in_liquid: bool,
+    /// Overlapping a climbable block during the last step.
+    pub on_ladder: bool,
+}
+
+impl Body {
+    pub fn new(position: Vec3, half_width: f32, height: f32) -> Self {
+        Body {
+            position,
+            velocity: Vec3::ZERO,
+            half_width,
+            height,
+            on_ground: false,
+            against_wall: false,
+            in_liquid: false,
+            on_ladder: false,
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let half = Vec3::new(self.half_width, 0.0, self.half_width);
+        Aabb::new(self.position - half, self.position + half + Vec3::Y * self.height)
+    }
+
+    /// Advances the body by `dt` seconds: accelerates it towards
+    /// `intent.walk` with the grip of the ground or liquid it is in, applies
+    /// gravity, jumping, swimming and climbing, then moves it as far as the
+    /// terrain allows. On the ground it walks up ledges of up to
+    /// `STEP_HEIGHT`.
+    ///
+    /// Walking into a one block wide wall stops flush against it, while a
+    /// slab is stepped onto:
+    ///
+    /// ```
+    /// use metalcraft::math::{IVec3, Vec3};
+    /// use metalcraft::physics::{Body, BlockPhysics, Intent};
+    ///
+    /// let slab = BlockPhysics { height: 0.5, ..BlockPhysics::SOLID };
+    /// let terrain = |pos: IVec3| match (pos.x, pos.y) {
+    ///     (_, y) if y < 64 => BlockPhysics::SOLID,
+    ///     (4, 64) if pos.z == 0 => BlockPhysics::SOLID,
+    ///     (4, 64) if pos.z == 4 => slab,
+    ///     _ => BlockPhysics::AIR,
+    /// };
+    /// let east = Intent { walk: Vec3::new(4.3, 0.0, 0.0), ..Intent::default() };
+    ///
+    /// let mut body = Body::new(Vec3::new(0.5, 64.0, 0.5), 0.3, 1.8);
+    /// for _ in 0..180 {
+    ///     body.step(&terrain, &east, 1.0 / 60.0);
+    /// }
+    /// assert!((body.position.x - 3.7).abs() < 1e-4 && body.position.z == 0.5);
+    /// assert!(body.against_wall);
+    ///
+    /// let mut body = Body::new(Vec3::new(0.5, 64.0, 4.5), 0.3, 1.8);
+    /// for _ in 0..180 {
+    ///     body.step(&terrain, &east, 1.0 / 60.0);
+    /// }
+    /// assert!(body.position.x > 6.0 && body.position.y == 64.0);
+    /// ```
+    pub fn step(&mut self, terrain: &impl Terrain, intent: &Intent, dt: f32) {
+        self.in_liquid = false;
+        self.on_ladder = false;
+        for cell in self.aabb().cells() {
+            let block = terrain.physics(cell);
+            self.in_liquid |= block.liquid;
+            self.on_ladder |= block.climbable;
+        }
+
+        let (acceleration, speed) = if self.in_liquid {
+            (LIQUID_ACCELERATION, LIQUID_SPEED_FACTOR)
+        } else if self.on_ground {
+            (GROUND_ACCELERATION * self.ground_friction(terrain), 1.0)
+        } else {
+            (AIR_ACCELERATION, 1.0)
+        };
+        let blend = (acceleration * dt).min(1.0);
+        self.velocity.x += (intent.walk.x * speed - self.velocity.x) * blend;
+        self.velocity.z += (intent.walk.z * speed - self.velocity.z) * blend;
+
+        if self.in_liquid {
+            self.velocity.y -= GRAVITY * LIQUID_GRAVITY_FACTOR * dt;
+            if intent.jump {
+                self.velocity.y = SWIM_SPEED;
+            }
+            self.velocity.y = self.velocity.y.clamp(-SINK_SPEED, SWIM_SPEED);
+        } else {
+            if intent.jump && self.on_ground {
+                self.velocity.y = JUMP_SPEED;
+            }
+            self.velocity.y = (self.velocity.y - GRAVITY * dt).max(-TERMINAL_VELOCITY);
+        }
+        if self.on_ladder {
+            self.velocity.y = self.velocity.y.max(-CLIMB_SPEED);
+            // Sneaking holds on even while pushing against the wall, which
+            // would otherwise climb.
+            if intent.jump {
+                self.velocity.y = CLIMB_SPEED;
+            } else if intent.sneak {
+                self.velocity.y = 0.0;
+            } else if self.against_wall {
+                self.velocity.y = CLIMB_SPEED;
+            }
+        }
+
+        self.move_by(terrain, self.velocity * dt);
+    }
+
+    /// Friction of the block the body stands on, or 1.0 if it stands on
+    /// nothing in particular.
+    fn ground_friction(&self, terrain: &impl Terrain) -> f32 {
+        let below = Vec3::new(self.position.x, self.position.y - 0.001, self.position.z);
+        let block = terrain.physics(below.floor().to_ivec3());
+        if block.height > 0.0 {
+            block.friction
+        } else {
+            1.0
+        }
+    }
+
+    /// Moves by `motion` as far as the terrain allows, stepping up low
+    /// ledges when walking on the ground, and updates the contact flags
+    /// and velocity.
+    fn move_by(&mut self, terrain: &impl Terrain, motion: Vec3) {
+        let start = self.aabb();
+        let mut moved = sweep(terrain, start, motion);
+        let blocked_sideways = moved.x != motion.x || moved.z != motion.z;
+
+        if blocked_sideways && self.on_ground && motion.y <= 0.0 {
+            // Try the same move from STEP_HEIGHT up, then settle back down.
+            let up = sweep(terrain, start, Vec3::Y * STEP_HEIGHT);
+            let raised = start.offset(up);
+            let across = sweep(terrain, raised, Vec3::new(motion.x, 0.0, motion.z));
+            let down = sweep(terrain, raised.offset(across), Vec3::new(0.0, motion.y - up.y, 0.0));
+            let stepped = up + across + down;
+            let horizontal = |v: Vec3| v.x * v.x + v.z * v.z;
+            if horizontal(stepped) > horizontal(moved) {
+                moved = stepped;
+            }
+        }
+
+        self.position += moved;
+        self.against_wall = moved.x != motion.x || moved.z != motion.z;
+        self.on_ground = motion.y < 0.0 && moved.y != motion.y;
+        if moved.y != motion.y {
+            self.velocity.y = 0.0;
+        }
+        if moved.x != motion.x {
+            self.velocity.x = 0.0;
+        }
+        if moved.z != motion.z {
+            self.velocity.z = 0.0;
+        }
+    }
+}
+
+/// How much of `motion` `aabb` can make before hitting a block, moving
+/// along Y, then X, then Z.
+fn sweep(terrain: &impl Terrain, aabb: Aabb, motion: Vec3) -> Vec3 {
+    let reach = Aabb::new(aabb.min.min(aabb.min + motion), aabb.max.max(aabb.max + motion));
+    let boxes: Vec<Aabb> = reach.cells().filter_map(|cell| terrain.physics(cell).collision_box(cell)).collect();
+
+    let mut aabb = aabb;
+    let mut moved = Vec3::ZERO;
+    for axis in [1, 0, 2] {
+        let distance = boxes.iter().fold(motion[axis], |distance, block| clip(&aabb, block, axis, distance));
+        let offset = with_component(Vec3::ZERO, axis, distance);
+        aabb = aabb.offset(offset);
+        moved += offset;
+    }
+    moved
+}
+
+/// Shortens `distance` along `axis` so `aabb` stops at `block`, if `block`
+/// is in its path. Faces closer than `CONTACT` count as touching, so a box
+/// resting against a block after rounding neither sinks into it nor snags
+/// on it when sliding along.
+fn clip(aabb: &Aabb, block: &Aabb, axis: usize, distance: f32) -> f32 {
+    let overlaps = (0..3)
+        .filter(|&other| other != axis)
+        .all(|other| aabb.min[other] < block.max[other] - CONTACT && aabb.max[other] > block.min[other] + CONTACT);
+    if !overlaps {
+        distance
+    } else if distance > 0.0 && aabb.max[axis] <= block.min[axis] + CONTACT {
+        distance.min((block.min[axis] - aabb.max[axis]).max(0.0))
+    } else if distance < 0.0 && aabb.min[axis] >= block.max[axis] - CONTACT {
+        distance.max((block.max[axis] - aabb.min[axis]).min(0.0))
+    } else {
+        distance
+    }
+}
+
+fn with_component(mut v: Vec3, axis: usize, value: f32) -> Vec3 {
+    match axis {
+        0 => v.x = value,
+        1 => v.y = value,
+        _ => v.z = value,
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockRegistry;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    fn physics(name: &str) -> BlockPhysics {
+        let registry = BlockRegistry::builtin();
+        BlockPhysics::of(registry.def(registry.expect_id(name)))
+    }
+
+    fn player(x: f32, y: f32, z: f32) -> Body {
+        Body::new(Vec3::new(x, y, z), 0.3, 1.8)
+    }
+
+    /// Runs `seconds` of steps, returning the lowest and highest feet height.
+    fn run(body: &mut Body, terrain: &impl Terrain, intent: &Intent, seconds: f32) -> (f32, f32) {
+        let (mut low, mut high) = (f32::MAX, f32::MIN);
+        for _ in 0..(seconds / DT).round() as u32 {
+            body.step(terrain, intent, DT);
+            low = low.min(body.position.y);
+            high = high.max(body.position.y);
+        }
+        (low, high)
+    }
+
+    /// A stone floor with its top at y = 60 under water up to y = 65.
+    fn pool() -> impl Fn(IVec3) -> BlockPhysics {
+        let water = physics("water");
+        move |pos: IVec3| match pos.y {
+            y if y < 60 => BlockPhysics::SOLID,
+            60..=64 => water,
+            _ => BlockPhysics::AIR,
+        }
+    }
+
+    #[test]
+    fn sinks_to_the_pool_floor() {
+        let terrain = pool();
+        let mut body = player(0.5, 64.0, 0.5);
+        run(&mut body, &terrain, &Intent::default(), 0.5);
+        assert!(body.in_liquid);
+        assert_eq!(body.velocity.y, -SINK_SPEED);
+
+        run(&mut body, &terrain, &Intent::default(), 2.0);
+        assert_eq!(body.position.y, 60.0);
+        assert!(body.on_ground && body.in_liquid);
+    }
+
+    #[test]
+    fn swims_up_and_stays_at_the_surface() {
+        let terrain = pool();
+        let mut body = player(0.5, 60.0, 0.5);
+        let swim = Intent { jump: true, ..Intent::default() };
+        run(&mut body, &terrain, &swim, 0.5);
+        assert_eq!(body.velocity.y, SWIM_SPEED);
+
+        // Bobs with the feet just above the water; the box never leaves it.
+        run(&mut body, &terrain, &swim, 3.0);
+        let (low, high) = run(&mut body, &terrain, &swim, 2.0);
+        assert!(low > 64.0 && high < 65.5, "{}..{}", low, high);
+    }
+
+    /// A floor with its top at y = 64, a ladder column at x = 1 in front of
+    /// a wall at x = 2, from y = 64 up to y = 74.
+    fn ladder_wall() -> impl Fn(IVec3) -> BlockPhysics {
+        let ladder = physics("ladder");
+        move |pos: IVec3| match (pos.x, pos.y) {
+            (_, y) if y < 64 => BlockPhysics::SOLID,
+            (1, 64..=74) => ladder,
+            (2, 64..=74) => BlockPhysics::SOLID,
+            _ => BlockPhysics::AIR,
+        }
+    }
+
+    #[test]
+    fn climbs_a_ladder() {
+        let terrain = ladder_wall();
+        let mut body = player(1.5, 64.0, 0.5);
+        let up = Intent { jump: true, ..Intent::default() };
+        run(&mut body, &terrain, &up, 1.0);
+        assert!(body.on_ladder);
+        assert!((body.position.y - (64.0 + CLIMB_SPEED)).abs() < 0.01, "y = {}", body.position.y);
+
+        // Walking into the wall climbs too.
+        let mut body = player(1.5, 64.0, 0.5);
+        let east = Intent { walk: Vec3::new(4.3, 0.0, 0.0), ..Intent::default() };
+        run(&mut body, &terrain, &east, 1.0);
+        assert!(body.against_wall && body.position.y > 66.0, "y = {}", body.position.y);
+
+        // Letting go slides down no faster than climbing.
+        let (low, _) = run(&mut body, &terrain, &Intent::default(), 0.5);
+        assert!(body.velocity.y >= -CLIMB_SPEED);
+        assert!(low < 66.0 && low > 66.0 - CLIMB_SPEED);
+    }
+
+    #[test]
+    fn sneaking_holds_on_to_a_ladder() {
+        let terrain = ladder_wall();
+        let up = Intent { jump: true, ..Intent::default() };
+        for walk in [Vec3::ZERO, Vec3::new(4.3, 0.0, 0.0)] {
+            let mut body = player(1.5, 64.0, 0.5);
+            run(&mut body, &terrain, &Intent { walk, ..up }, 1.0);
+            let held = body.position.y;
+
+            let hold = Intent { walk, sneak: true, ..Intent::default() };
+            let (low, high) = run(&mut body, &terrain, &hold, 1.0);
+            assert_eq!((low, high), (held, held), "walking {:?}", walk);
+        }
+    }
+
+    /// How far a body walking east over `ground` slides after it stops walking.
+    fn slide(ground: BlockPhysics) -> f32 {
+        let terrain = move |pos: IVec3| if pos.y < 64 { ground } else { BlockPhysics::AIR };
+        let mut body = player(0.5, 64.0, 0.5);
+        run(&mut body, &terrain, &Intent { walk: Vec3::new(4.3, 0.0, 0.0), ..Intent::default() }, 1.0);
+        let stop = body.position.x;
+        run(&mut body, &terrain, &Intent::default(), 6.0);
+        assert!(body.velocity.x.abs() < 0.01);
+        body.position.x - stop
+    }
+
+    #[test]
+    fn ice_slides_further_than_stone() {
+        let (stone, ice) = (slide(physics("stone")), slide(physics("ice")));
+        assert!(stone > 0.0 && stone < 0.5, "stone {}", stone);
+        assert!(ice > 5.0 * stone, "ice {} vs stone {}", ice, stone);
+    }
+
+    #[test]
+    fn no_tunnelling_at_large_steps() {
+        // A single floor block layer with its top at y = 11 and nothing below.
+        let floor = |pos: IVec3| if pos.y == 10 { BlockPhysics::SOLID } else { BlockPhysics::AIR };
+        for dt in [0.25, 0.5, 1.0] {
+            let mut body = player(0.5, 12.0, 0.5);
+            body.velocity.y = -TERMINAL_VELOCITY;
+            body.step(&floor, &Intent::default(), dt);
+            assert_eq!(body.position.y, 11.0, "dt = {}", dt);
+            assert!(body.on_ground);
+        }
+
+        // Nor through a wall one block thick.
+        let wall = |pos: IVec3| if pos.x == 5 || pos.y < 64 { BlockPhysics::SOLID } else { BlockPhysics::AIR };
+        let mut body = player(0.5, 64.0, 0.5);
+        body.velocity.x = 200.0;
+        body.step(&wall, &Intent { walk: Vec3::new(200.0, 0.0, 0.0), ..Intent::default() }, 0.5);
+        assert_eq!(body.position.x, 4.7);
+        assert!(body.against_wall);
+    }
+}
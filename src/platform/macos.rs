@@ -0,0 +1,19 @@
+use metal::MetalLayerRef;
+use objc::runtime::Object;
+use winit::platform::macos::{WindowBuilderExtMacOS, WindowExtMacOS};
+use winit::window::{Window, WindowBuilder};
+
+/// Applies the macOS-specific window options.
+pub fn configure_window(builder: WindowBuilder) -> WindowBuilder {
+    builder.with_movable_by_window_background(true)
+}
+
+/// Makes `layer` the backing layer of the window's content view.
+pub fn attach_layer(window: &Window, layer: &MetalLayerRef) {
+    unsafe {
+        let ns_window: *mut Object = window.ns_window() as *mut _;
+        let ns_view: *mut Object = msg_send![ns_window, contentView];
+        let _: () = msg_send![ns_view, setLayer: layer];
+        let _: () = msg_send![ns_view, setWantsLayer: true];
+    }
+}
@@ -0,0 +1,5 @@
+//! Platform glue that cannot be written portably. Everything outside this
+//! module (and the Metal renderer) builds on any target.
+
+#[cfg(target_os = "macos")]
+pub mod macos;
@@ -0,0 +1,324 @@
+//! The first-person player: walking, sprinting, sneaking, jumping and mouse
+//! look.
+//!
+//! Movement advances in fixed ticks of `TICK` so it behaves the same at any
+//! frame rate; the camera is placed between the last two ticks. Looking
+//! around is applied every frame instead, straight from the mouse. Falling,
+//! collisions, swimming and climbing are up to the player's physics `Body`.
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::time::Duration;
+
+use crate::camera::Camera;
+use crate::input::{Action, InputState};
+use crate::math::Vec3;
+use crate::physics::{Body, Intent, Terrain};
+
+/// Movement ticks per second.
+pub const TICK_RATE: u32 = 60;
+/// Length of one movement tick.
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / TICK_RATE as u64);
+
+/// Speeds in blocks per second.
+pub const WALK_SPEED: f32 = 4.3;
+pub const SPRINT_SPEED: f32 = 5.6;
+pub const SNEAK_SPEED: f32 = 1.3;
+/// Size of the player's collision box.
+pub const WIDTH: f32 = 0.6;
+pub const HEIGHT: f32 = 1.8;
+/// Eye height above the feet, standing and sneaking.
+pub const EYE_HEIGHT: f32 = 1.62;
+pub const SNEAK_EYE_HEIGHT: f32 = 1.27;
+
+/// Radians turned per unit of raw mouse motion.
+pub const DEFAULT_SENSITIVITY: f32 = 0.0025;
+
+/// Ticks run for one frame at most; after a long stall the game slows down
+/// rather than trying to catch up all at once.
+const MAX_TICKS_PER_FRAME: u32 = 10;
+
+/// What the player wants to do during one tick, read from the controls.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerInput {
+    /// +1 forward, -1 back.
+    pub forward: f32,
+    /// +1 right, -1 left.
+    pub strafe: f32,
+    pub jump: bool,
+    pub sneak: bool,
+    pub sprint: bool,
+}
+
+impl PlayerInput {
+    pub fn from_input(input: &InputState) -> Self {
+        let axis = |positive, negative| input.is_held(positive) as i32 as f32 - input.is_held(negative) as i32 as f32;
+        PlayerInput {
+            forward: axis(Action::MoveForward, Action::MoveBack),
+            strafe: axis(Action::MoveRight, Action::MoveLeft),
+            jump: input.is_held(Action::Jump),
+            sneak: input.is_held(Action::Sneak),
+            sprint: input.is_held(Action::Sprint),
+        }
+    }
+}
+
+/// Splits real time into whole movement ticks, carrying the remainder over
+/// to the next frame.
+#[derive(Clone, Debug, Default)]
+pub struct FixedTimestep {
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// Adds `elapsed` real time and returns how many ticks are due.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        let ticks = (self.accumulator.as_nanos() / TICK.as_nanos()) as u32;
+        self.accumulator -= TICK * ticks;
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.accumulator = Duration::ZERO;
+        }
+        ticks.min(MAX_TICKS_PER_FRAME)
+    }
+
+    /// How far into the next tick the carried-over time reaches, in 0.0..1.0.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / TICK.as_secs_f32()
+    }
+}
+
+/// The player: a physics body with a view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    /// Position is at the feet.
+    pub body: Body,
+    /// Body position before the last tick, for interpolating the camera.
+    previous_position: Vec3,
+    /// Same convention as `Camera`: radians around +Y, 0 looking down -Z.
+    pub yaw: f32,
+    pub pitch: f32,
+    sneaking: bool,
+    sprinting: bool,
+    pub sensitivity: f32,
+}
+
+impl Player {
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+
+    pub fn new(position: Vec3) -> Self {
+        Player {
+            body: Body::new(position, WIDTH / 2.0, HEIGHT),
+            previous_position: position,
+            yaw: 0.0,
+            pitch: 0.0,
+            sneaking: false,
+            sprinting: false,
+            sensitivity: DEFAULT_SENSITIVITY,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.body.position
+    }
+
+    pub fn is_sneaking(&self) -> bool {
+        self.sneaking
+    }
+
+    pub fn is_sprinting(&self) -> bool {
+        self.sprinting
+    }
+
+    pub fn eye_height(&self) -> f32 {
+        if self.sneaking {
+            SNEAK_EYE_HEIGHT
+        } else {
+            EYE_HEIGHT
+        }
+    }
+
+    /// Moves the player to `position` without interpolating from the old one.
+    pub fn teleport(&mut self, position: Vec3) {
+        self.body.position = position;
+        self.body.velocity = Vec3::ZERO;
+        self.previous_position = position;
+    }
+
+    /// Turns the view by raw mouse motion, +Y down.
+    pub fn look(&mut self, dx: f64, dy: f64) {
+        self.yaw = (self.yaw + dx as f32 * self.sensitivity).rem_euclid(TAU);
+        self.pitch = (self.pitch - dy as f32 * self.sensitivity).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    }
+
+    /// Advances the player by one `TICK` through `terrain`.
+    pub fn tick(&mut self, input: &PlayerInput, terrain: &impl Terrain) {
+        self.previous_position = self.body.position;
+
+        self.sneaking = input.sneak;
+        // Sprinting needs forward movement and stops as soon as it ends.
+        self.sprinting = input.sprint && input.forward > 0.0 && !self.sneaking;
+        let speed = if self.sneaking {
+            SNEAK_SPEED
+        } else if self.sprinting {
+            SPRINT_SPEED
+        } else {
+            WALK_SPEED
+        };
+
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let forward = Vec3::new(sin_yaw, 0.0, -cos_yaw);
+        let right = Vec3::new(cos_yaw, 0.0, sin_yaw);
+        let mut wish = forward * input.forward + right * input.strafe;
+        if wish.length_squared() > 1.0 {
+            wish = wish.normalize();
+        }
+
+        let intent = Intent { walk: wish * speed, jump: input.jump, sneak: input.sneak };
+        self.body.step(terrain, &intent, TICK.as_secs_f32());
+    }
+
+    /// Eye position `alpha` of the way from the previous tick to the last.
+    pub fn eye_position(&self, alpha: f32) -> Vec3 {
+        self.previous_position.lerp(self.body.position, alpha) + Vec3::Y * self.eye_height()
+    }
+
+    /// Points `camera` out of the player's eyes.
+    pub fn update_camera(&self, camera: &mut Camera, alpha: f32) {
+        camera.position = self.eye_position(alpha);
+        camera.set_rotation(self.yaw, self.pitch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::IVec3;
+    use crate::physics::BlockPhysics;
+
+    use super::*;
+
+    /// Solid ground with its top at y = 0.
+    fn flat(pos: IVec3) -> BlockPhysics {
+        if pos.y < 0 {
+            BlockPhysics::SOLID
+        } else {
+            BlockPhysics::AIR
+        }
+    }
+
+    /// Horizontal speed after holding `input` for a second on flat ground.
+    fn speed_with(input: PlayerInput) -> (f32, Player) {
+        let mut player = Player::new(Vec3::new(0.5, 0.0, 0.5));
+        for _ in 0..TICK_RATE {
+            player.tick(&input, &flat);
+        }
+        let velocity = player.body.velocity;
+        (Vec3::new(velocity.x, 0.0, velocity.z).length(), player)
+    }
+
+    #[test]
+    fn ticks_do_not_depend_on_frame_length() {
+        let total = |frame: Duration, frames: u32| {
+            let mut timestep = FixedTimestep::default();
+            (0..frames).map(|_| timestep.advance(frame)).sum::<u32>()
+        };
+        assert_eq!(total(Duration::from_millis(1), 1000), TICK_RATE);
+        assert_eq!(total(Duration::from_millis(100), 10), TICK_RATE);
+        assert_eq!(total(Duration::from_micros(16_500), 100), total(Duration::from_millis(33), 50));
+    }
+
+    #[test]
+    fn remainder_carries_over() {
+        let mut timestep = FixedTimestep::default();
+        assert_eq!(timestep.advance(TICK / 2), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(TICK * 3 / 4), 1);
+        assert!((timestep.alpha() - 0.25).abs() < 1e-6);
+
+        for millis in [1, 7, 16, 17, 33, 50, 99] {
+            timestep.advance(Duration::from_millis(millis));
+            assert!((0.0..1.0).contains(&timestep.alpha()), "{}", timestep.alpha());
+        }
+    }
+
+    #[test]
+    fn long_stall_is_clamped() {
+        let mut timestep = FixedTimestep::default();
+        timestep.advance(TICK / 2);
+        assert_eq!(timestep.advance(Duration::from_secs(2)), MAX_TICKS_PER_FRAME);
+        // The backlog is dropped rather than replayed over the next frames.
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(TICK / 2), 0);
+    }
+
+    #[test]
+    fn walk_sprint_and_sneak_speeds() {
+        let walk = PlayerInput { forward: 1.0, ..Default::default() };
+        let close = |speed: f32, expected: f32| (speed - expected).abs() < 0.01;
+
+        let (speed, player) = speed_with(walk);
+        assert!(close(speed, WALK_SPEED), "{}", speed);
+        assert!(player.body.on_ground && !player.is_sprinting());
+
+        let (speed, player) = speed_with(PlayerInput { sprint: true, ..walk });
+        assert!(close(speed, SPRINT_SPEED), "{}", speed);
+        assert!(player.is_sprinting());
+
+        let (speed, player) = speed_with(PlayerInput { sneak: true, ..walk });
+        assert!(close(speed, SNEAK_SPEED), "{}", speed);
+        assert!(player.is_sneaking() && player.eye_height() == SNEAK_EYE_HEIGHT);
+
+        // Diagonal movement is no faster.
+        let (speed, _) = speed_with(PlayerInput { strafe: 1.0, ..walk });
+        assert!(close(speed, WALK_SPEED), "{}", speed);
+    }
+
+    #[test]
+    fn sprint_needs_forward_movement_and_no_sneaking() {
+        let sprint = PlayerInput { sprint: true, ..Default::default() };
+        for input in [
+            PlayerInput { strafe: 1.0, ..sprint },
+            PlayerInput { forward: -1.0, ..sprint },
+            PlayerInput { forward: 1.0, sneak: true, ..sprint },
+        ] {
+            let (speed, player) = speed_with(input);
+            assert!(!player.is_sprinting(), "{:?}", input);
+            assert!(speed < WALK_SPEED + 0.01, "{:?}: {}", input, speed);
+        }
+    }
+
+    #[test]
+    fn look_clamps_pitch_and_wraps_yaw() {
+        let mut player = Player::new(Vec3::ZERO);
+        player.sensitivity = 0.01;
+        player.look(0.0, -1000.0);
+        assert_eq!(player.pitch, Player::MAX_PITCH);
+        player.look(0.0, 5000.0);
+        assert_eq!(player.pitch, -Player::MAX_PITCH);
+
+        player.look(-100.0, 0.0);
+        assert!((player.yaw - (TAU - 1.0)).abs() < 1e-5, "{}", player.yaw);
+        player.look(700.0, 0.0);
+        assert!((0.0..TAU).contains(&player.yaw));
+        assert!((player.yaw - 6.0).abs() < 1e-4, "{}", player.yaw);
+    }
+
+    #[test]
+    fn jump_leaves_the_ground() {
+        let mut player = Player::new(Vec3::new(0.5, 0.0, 0.5));
+        player.tick(&PlayerInput::default(), &flat);
+        assert!(player.body.on_ground);
+
+        let jump = PlayerInput { jump: true, ..Default::default() };
+        player.tick(&jump, &flat);
+        let mut peak: f32 = 0.0;
+        for _ in 0..TICK_RATE / 2 {
+            player.tick(&PlayerInput::default(), &flat);
+            peak = peak.max(player.position().y);
+        }
+        assert!(peak > 1.0, "peak {}", peak);
+        for _ in 0..TICK_RATE {
+            player.tick(&PlayerInput::default(), &flat);
+        }
+        assert!(player.body.on_ground && player.position().y == 0.0);
+    }
+}
@@ -4,20 +4,47 @@ using namespace metal;
 struct VertexIn {
     float4 position [[attribute(0)]];
     float4 color [[attribute(1)]];
+    float3 normal [[attribute(2)]];
+    float2 uv [[attribute(3)]];
+    float light [[attribute(4)]];
+    float ao [[attribute(5)]];
+    float sky_light [[attribute(6)]];
+    float block_light [[attribute(7)]];
 };
 
 struct VertexOut {
     float4 position [[position]];
     float4 color;
+    float2 uv;
+    float light;
+    float ao;
+    float sky_light;
+    float block_light;
 };
 
-vertex VertexOut vertex_main(VertexIn in [[stage_in]]) {
+struct Uniforms {
+    float4x4 view;
+    float4x4 projection;
+    float4 sky_light;
+    float4 block_light;
+};
+
+vertex VertexOut vertex_main(VertexIn in [[stage_in]],
+                             constant Uniforms &uniforms [[buffer(1)]]) {
     VertexOut out;
-    out.position = in.position;
+    out.position = uniforms.projection * uniforms.view * in.position;
     out.color = in.color;
+    out.uv = in.uv;
+    out.light = in.light;
+    out.ao = in.ao;
+    out.sky_light = in.sky_light;
+    out.block_light = in.block_light;
     return out;
 }
 
-fragment float4 fragment_main(VertexOut in [[stage_in]]) {
-    return in.color;
+fragment float4 fragment_main(VertexOut in [[stage_in]],
+                              constant Uniforms &uniforms [[buffer(1)]]) {
+    // The brighter of daylight and torchlight, per channel.
+    float3 light = max(in.sky_light * uniforms.sky_light.rgb, in.block_light * uniforms.block_light.rgb);
+    return float4(in.color.rgb * light * in.light * in.ao, in.color.a);
 }
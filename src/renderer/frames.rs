@@ -0,0 +1,153 @@
+//! Frames-in-flight bookkeeping shared by the GPU backends.
+//!
+//! A backend keeps one `FrameContext`-like value per frame in a `FrameRing`
+//! and guards reuse with a `FrameSemaphore`: `acquire` before writing into the
+//! next context, `release` from the GPU completion callback of the frame that
+//! used it. With N contexts the CPU can run at most N frames ahead.
+
+use std::sync::{Condvar, Mutex};
+
+/// Default number of frames the CPU may encode ahead of the GPU.
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 3;
+
+/// Counting semaphore, released from GPU completion handlers.
+#[derive(Debug)]
+pub struct FrameSemaphore {
+    available: Mutex<usize>,
+    signal: Condvar,
+}
+
+impl FrameSemaphore {
+    pub fn new(count: usize) -> Self {
+        FrameSemaphore {
+            available: Mutex::new(count),
+            signal: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a frame slot is free and takes it.
+    pub fn acquire(&self) {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.signal.wait(available).unwrap();
+        }
+        *available -= 1;
+    }
+
+    /// Returns a frame slot. Safe to call from any thread.
+    pub fn release(&self) {
+        *self.available.lock().unwrap() += 1;
+        self.signal.notify_one();
+    }
+
+    pub fn available(&self) -> usize {
+        *self.available.lock().unwrap()
+    }
+}
+
+/// Fixed ring of per-frame resources, handed out round-robin.
+#[derive(Debug)]
+pub struct FrameRing<T> {
+    frames: Vec<T>,
+    current: usize,
+}
+
+impl<T> FrameRing<T> {
+    pub fn new(count: usize, make: impl FnMut(usize) -> T) -> Self {
+        assert!(count > 0, "need at least one frame in flight");
+        FrameRing {
+            frames: (0..count).map(make).collect(),
+            current: count - 1,
+        }
+    }
+
+    /// Moves to the next slot and returns it. Only reuse it once the GPU is done with it.
+    pub fn advance(&mut self) -> &mut T {
+        self.current = (self.current + 1) % self.frames.len();
+        &mut self.frames[self.current]
+    }
+
+    pub fn current(&self) -> &T {
+        &self.frames[self.current]
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn ring_starts_at_the_first_slot_and_wraps() {
+        let mut ring = FrameRing::new(3, |i| i * 10);
+        assert_eq!(ring.len(), 3);
+        let visited: Vec<_> = (0..7).map(|_| *ring.advance()).collect();
+        assert_eq!(visited, [0, 10, 20, 0, 10, 20, 0]);
+    }
+
+    #[test]
+    fn current_follows_advance() {
+        let mut ring = FrameRing::new(2, |i| i);
+        for expected in [0, 1, 0, 1] {
+            ring.advance();
+            assert_eq!(ring.current_index(), expected);
+            assert_eq!(*ring.current(), expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one frame")]
+    fn empty_ring_panics() {
+        FrameRing::new(0, |i| i);
+    }
+
+    #[test]
+    fn acquire_and_release_count_slots() {
+        let frames = FrameSemaphore::new(DEFAULT_FRAMES_IN_FLIGHT);
+        frames.acquire();
+        frames.acquire();
+        assert_eq!(frames.available(), 1);
+        frames.release();
+        assert_eq!(frames.available(), 2);
+    }
+
+    #[test]
+    fn acquire_blocks_until_a_frame_is_released() {
+        let frames = Arc::new(FrameSemaphore::new(2));
+        frames.acquire();
+        frames.acquire();
+
+        let (sender, receiver) = mpsc::channel();
+        let waiter = {
+            let frames = Arc::clone(&frames);
+            thread::spawn(move || {
+                frames.acquire();
+                sender.send(()).unwrap();
+            })
+        };
+        // Every frame is in flight: the CPU must wait for the GPU.
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+        // The GPU finishes one, as from a completion handler on another thread.
+        frames.release();
+        receiver.recv_timeout(Duration::from_secs(5)).expect("acquire did not wake up");
+        waiter.join().unwrap();
+        assert_eq!(frames.available(), 0);
+    }
+}
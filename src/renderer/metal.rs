@@ -0,0 +1,267 @@
+use std::fs::read_to_string;
+use std::sync::Arc;
+use block::ConcreteBlock;
+use core_graphics_types::geometry::CGSize;
+use metal::*;
+
+use crate::mesh::{Mesh, Vertex};
+use crate::vertex::{check_shader_inputs, AttributeFormat, VertexFormat};
+use super::frames::{FrameRing, FrameSemaphore};
+use super::{MeshHandle, Renderer, RendererConfig, Uniforms};
+
+/// Bytes reserved per frame for uniforms written by the CPU.
+const UNIFORM_BUFFER_SIZE: u64 = 4096;
+const _: () = assert!(std::mem::size_of::<Uniforms>() as u64 <= UNIFORM_BUFFER_SIZE);
+
+const COLOR_FORMAT: MTLPixelFormat = MTLPixelFormat::BGRA8Unorm;
+const DEPTH_FORMAT: MTLPixelFormat = MTLPixelFormat::Depth32Float;
+
+struct GpuMesh {
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u64,
+}
+
+/// Per-frame resources, reused once the GPU has finished the frame that last used them.
+struct FrameContext {
+    uniform_buffer: Buffer,
+}
+
+/// State that only lives between `begin_frame` and `present`.
+struct Frame {
+    drawable: MetalDrawable,
+    command_buffer: CommandBuffer,
+    encoder: RenderCommandEncoder,
+}
+
+/// `Renderer` backed by Metal, drawing into a `CAMetalLayer`.
+///
+/// The layer still has to be attached to a view by the caller; see `layer()`.
+/// At most `RendererConfig::frames_in_flight` frames are queued on the GPU;
+/// `begin_frame` blocks until the oldest one has completed.
+pub struct MetalRenderer {
+    device: Device,
+    layer: MetalLayer,
+    command_queue: CommandQueue,
+    pipeline_state: RenderPipelineState,
+    depth_state: DepthStencilState,
+    /// Matches the drawable size; recreated whenever that changes.
+    depth_texture: Option<Texture>,
+    frames: FrameRing<FrameContext>,
+    in_flight: Arc<FrameSemaphore>,
+    meshes: Vec<GpuMesh>,
+    frame: Option<Frame>,
+}
+
+impl MetalRenderer {
+    pub fn new() -> Self {
+        Self::with_config(RendererConfig::default())
+    }
+
+    pub fn with_config(config: RendererConfig) -> Self {
+        let device = Device::system_default().expect("No Metal device found");
+        let layer = MetalLayer::new();
+        layer.set_device(&device);
+        layer.set_pixel_format(COLOR_FORMAT);
+        layer.set_presents_with_transaction(false);
+
+        // Create a simple vertex shader and fragment shader
+        let shader_source = read_to_string("src/render.metal").expect("Failed to read render.metal file");
+
+        // Compile the shader code
+        let library = device.new_library_with_source(&shader_source, &CompileOptions::new())
+            .expect("Failed to compile Metal shader");
+        let vertex_function = library.get_function("vertex_main", None).unwrap();
+        let fragment_function = library.get_function("fragment_main", None).unwrap();
+
+        check_shader_inputs::<Vertex>(&shader_source, "VertexIn")
+            .unwrap_or_else(|e| panic!("render.metal does not match Vertex: {}", e));
+        let vertex_descriptor = vertex_descriptor::<Vertex>();
+
+        // Create a render pipeline
+        let pipeline_descriptor = RenderPipelineDescriptor::new();
+        pipeline_descriptor.set_vertex_function(Some(&vertex_function));
+        pipeline_descriptor.set_fragment_function(Some(&fragment_function));
+        pipeline_descriptor.set_vertex_descriptor(Some(vertex_descriptor));
+        pipeline_descriptor.color_attachments().object_at(0).unwrap().set_pixel_format(COLOR_FORMAT);
+        pipeline_descriptor.set_depth_attachment_pixel_format(DEPTH_FORMAT);
+
+        let pipeline_state = device.new_render_pipeline_state(&pipeline_descriptor)
+            .expect("Failed to create render pipeline state");
+
+        let depth_descriptor = DepthStencilDescriptor::new();
+        depth_descriptor.set_depth_compare_function(MTLCompareFunction::LessEqual);
+        depth_descriptor.set_depth_write_enabled(true);
+        let depth_state = device.new_depth_stencil_state(&depth_descriptor);
+
+        let command_queue = device.new_command_queue();
+        let frames = FrameRing::new(config.frames_in_flight, |_| FrameContext {
+            uniform_buffer: device.new_buffer(UNIFORM_BUFFER_SIZE, MTLResourceOptions::CPUCacheModeWriteCombined),
+        });
+        let in_flight = Arc::new(FrameSemaphore::new(config.frames_in_flight));
+
+        MetalRenderer {
+            device,
+            layer,
+            command_queue,
+            pipeline_state,
+            depth_state,
+            depth_texture: None,
+            frames,
+            in_flight,
+            meshes: Vec::new(),
+            frame: None,
+        }
+    }
+
+    /// The layer frames are presented to. Attach it to the window's content view.
+    pub fn layer(&self) -> &MetalLayerRef {
+        &self.layer
+    }
+
+    /// Matches the layer to the display's backing scale (2.0 on Retina) so
+    /// drawables are rendered at native resolution instead of upscaled.
+    pub fn set_scale_factor(&self, scale_factor: f64) {
+        self.layer.set_contents_scale(scale_factor);
+    }
+
+    /// Returns a depth texture of exactly `width` x `height`, recreating it if the size changed.
+    fn depth_target(&mut self, width: u64, height: u64) -> &TextureRef {
+        let stale = match &self.depth_texture {
+            Some(texture) => texture.width() != width || texture.height() != height,
+            None => true,
+        };
+        if stale {
+            let descriptor = TextureDescriptor::new();
+            descriptor.set_pixel_format(DEPTH_FORMAT);
+            descriptor.set_width(width);
+            descriptor.set_height(height);
+            descriptor.set_storage_mode(MTLStorageMode::Private);
+            descriptor.set_usage(MTLTextureUsage::RenderTarget);
+            self.depth_texture = Some(self.device.new_texture(&descriptor));
+        }
+        self.depth_texture.as_ref().unwrap()
+    }
+
+    fn new_buffer<T>(&self, data: &[T]) -> Buffer {
+        self.device.new_buffer_with_data(
+            data.as_ptr() as *const _,
+            std::mem::size_of_val(data) as u64,
+            MTLResourceOptions::CPUCacheModeDefaultCache,
+        )
+    }
+}
+
+/// Builds the Metal vertex descriptor for `V`, reading everything from buffer 0.
+fn vertex_descriptor<V: VertexFormat>() -> &'static VertexDescriptorRef {
+    let descriptor = VertexDescriptor::new();
+    for attribute in V::ATTRIBUTES {
+        let slot = descriptor.attributes().object_at(attribute.index as u64).unwrap();
+        slot.set_format(metal_format(attribute.format));
+        slot.set_offset(attribute.offset as u64);
+        slot.set_buffer_index(0);
+    }
+
+    let layout = descriptor.layouts().object_at(0).unwrap();
+    layout.set_stride(V::stride() as u64);
+    layout.set_step_function(MTLVertexStepFunction::PerVertex);
+    layout.set_step_rate(1);
+    descriptor
+}
+
+fn metal_format(format: AttributeFormat) -> MTLVertexFormat {
+    match format {
+        AttributeFormat::Float => MTLVertexFormat::Float,
+        AttributeFormat::Float2 => MTLVertexFormat::Float2,
+        AttributeFormat::Float3 => MTLVertexFormat::Float3,
+        AttributeFormat::Float4 => MTLVertexFormat::Float4,
+        AttributeFormat::UChar4Normalized => MTLVertexFormat::UChar4Normalized,
+    }
+}
+
+impl Default for MetalRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for MetalRenderer {
+    fn resize(&mut self, width: u32, height: u32) {
+        self.layer.set_drawable_size(CGSize::new(width as f64, height as f64));
+        self.depth_target(width as u64, height as u64);
+    }
+
+    fn begin_frame(&mut self, clear_color: [f32; 4], uniforms: &Uniforms) {
+        assert!(self.frame.is_none(), "begin_frame called twice without present");
+
+        // Wait until the GPU is done with the context we are about to overwrite.
+        self.in_flight.acquire();
+        let context = self.frames.advance();
+        unsafe {
+            std::ptr::copy_nonoverlapping(uniforms, context.uniform_buffer.contents() as *mut Uniforms, 1);
+        }
+
+        let drawable = self.layer.next_drawable().unwrap().to_owned();
+        let render_pass_descriptor = RenderPassDescriptor::new();
+        let color_attachment = render_pass_descriptor.color_attachments().object_at(0).unwrap();
+        color_attachment.set_texture(Some(drawable.texture()));
+        color_attachment.set_load_action(MTLLoadAction::Clear);
+        let [r, g, b, a] = clear_color.map(f64::from);
+        color_attachment.set_clear_color(MTLClearColor::new(r, g, b, a));
+        color_attachment.set_store_action(MTLStoreAction::Store);
+
+        let (width, height) = (drawable.texture().width(), drawable.texture().height());
+        let depth_attachment = render_pass_descriptor.depth_attachment().unwrap();
+        depth_attachment.set_texture(Some(self.depth_target(width, height)));
+        depth_attachment.set_load_action(MTLLoadAction::Clear);
+        depth_attachment.set_clear_depth(1.0);
+        depth_attachment.set_store_action(MTLStoreAction::DontCare);
+
+        let command_buffer = self.command_queue.new_command_buffer().to_owned();
+        let in_flight = self.in_flight.clone();
+        let completed = ConcreteBlock::new(move |_: &CommandBufferRef| in_flight.release()).copy();
+        command_buffer.add_completed_handler(&completed);
+
+        let encoder = command_buffer.new_render_command_encoder(render_pass_descriptor).to_owned();
+        encoder.set_render_pipeline_state(&self.pipeline_state);
+        encoder.set_depth_stencil_state(&self.depth_state);
+        // Per-frame uniforms are always bound at buffer(1), in both stages.
+        encoder.set_vertex_buffer(1, Some(&self.frames.current().uniform_buffer), 0);
+        encoder.set_fragment_buffer(1, Some(&self.frames.current().uniform_buffer), 0);
+
+        self.frame = Some(Frame { drawable, command_buffer, encoder });
+    }
+
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshHandle {
+        let gpu_mesh = GpuMesh {
+            vertex_buffer: self.new_buffer(&mesh.vertices),
+            index_buffer: self.new_buffer(&mesh.indices),
+            index_count: mesh.indices.len() as u64,
+        };
+        self.meshes.push(gpu_mesh);
+        MeshHandle(self.meshes.len() - 1)
+    }
+
+    fn draw(&mut self, mesh: MeshHandle) {
+        let frame = self.frame.as_ref().expect("draw called outside of a frame");
+        let mesh = &self.meshes[mesh.0];
+        if mesh.index_count == 0 {
+            return;
+        }
+        frame.encoder.set_vertex_buffer(0, Some(&mesh.vertex_buffer), 0);
+        frame.encoder.draw_indexed_primitives(
+            MTLPrimitiveType::Triangle,
+            mesh.index_count,
+            MTLIndexType::UInt32,
+            &mesh.index_buffer,
+            0,
+        );
+    }
+
+    fn present(&mut self) {
+        let frame = self.frame.take().expect("present called outside of a frame");
+        frame.encoder.end_encoding();
+        frame.command_buffer.present_drawable(&frame.drawable);
+        frame.command_buffer.commit();
+    }
+}
@@ -0,0 +1,94 @@
+pub mod frames;
+#[cfg(target_os = "macos")]
+pub mod metal;
+pub mod software;
+
+#[cfg(target_os = "macos")]
+pub use self::metal::MetalRenderer;
+pub use self::software::{DepthBuffer, Framebuffer, SoftwareRenderer};
+
+use crate::camera::Camera;
+use crate::math::Mat4;
+use crate::mesh::Mesh;
+use crate::sky::Sky;
+
+/// Options shared by all backends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RendererConfig {
+    /// How many frames the CPU may encode before waiting on the GPU.
+    pub frames_in_flight: usize,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        RendererConfig {
+            frames_in_flight: frames::DEFAULT_FRAMES_IN_FLIGHT,
+        }
+    }
+}
+
+/// Per-frame shader constants. Matches `struct Uniforms` in `render.metal`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Uniforms {
+    pub view: Mat4,
+    pub projection: Mat4,
+    /// RGB multiplier for vertex sky light; see `Sky::light`. W is unused.
+    pub sky_light: [f32; 4],
+    /// RGB color of block light at full brightness. W is unused.
+    pub block_light: [f32; 4],
+}
+
+/// Sky light at noon.
+pub const DAYLIGHT: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+/// Block light is warmer than daylight.
+pub const BLOCK_LIGHT: [f32; 4] = [1.0, 0.86, 0.66, 1.0];
+
+impl Uniforms {
+    /// Positions are used as clip-space coordinates unchanged, at noon.
+    pub const IDENTITY: Uniforms = Uniforms {
+        view: Mat4::IDENTITY,
+        projection: Mat4::IDENTITY,
+        sky_light: DAYLIGHT,
+        block_light: BLOCK_LIGHT,
+    };
+
+    /// The camera's view at noon.
+    pub fn from_camera(camera: &Camera) -> Self {
+        Uniforms {
+            view: camera.view_matrix(),
+            projection: camera.projection_matrix(),
+            ..Uniforms::IDENTITY
+        }
+    }
+
+    /// Lights the world as `sky` does.
+    pub fn with_sky(self, sky: &Sky) -> Self {
+        let [r, g, b] = sky.light;
+        Uniforms { sky_light: [r, g, b, 1.0], ..self }
+    }
+}
+
+impl Default for Uniforms {
+    fn default() -> Self {
+        Uniforms::IDENTITY
+    }
+}
+
+/// Opaque reference to a mesh that has been uploaded to a renderer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MeshHandle(pub(crate) usize);
+
+/// Everything the engine needs from a graphics backend.
+///
+/// A frame is `begin_frame`, any number of `draw` calls, then `present`.
+/// Meshes may be uploaded at any time and stay valid for the renderer's lifetime.
+/// Draws are depth tested (less-equal) against a depth target cleared to 1.0.
+pub trait Renderer {
+    /// Resizes the render targets (color and depth) to `width` x `height` pixels.
+    fn resize(&mut self, width: u32, height: u32);
+    fn begin_frame(&mut self, clear_color: [f32; 4], uniforms: &Uniforms);
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshHandle;
+    fn draw(&mut self, mesh: MeshHandle);
+    fn present(&mut self);
+}
@@ -0,0 +1,376 @@
+use crate::mesh::{Mesh, Vertex};
+use crate::math::Mat4;
+use super::{MeshHandle, Renderer, Uniforms};
+
+/// In-memory RGBA8 color target, row-major with the first row at the top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4, "pixel buffer size mismatch");
+        Framebuffer { width, height, pixels }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Raw RGBA8 bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let i = self.index(x, y);
+        self.pixels[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    pub fn clear(&mut self, rgba: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel ({}, {}) out of bounds", x, y);
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+}
+
+/// Depth target holding clip-space z/w (0.0 at the near plane, 1.0 at the far plane).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthBuffer {
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+}
+
+impl DepthBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        DepthBuffer {
+            width,
+            height,
+            values: vec![1.0; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn depth(&self, x: u32, y: u32) -> f32 {
+        self.values[self.index(x, y)]
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn clear(&mut self, depth: f32) {
+        self.values.fill(depth);
+    }
+
+    /// Less-equal depth test; writes and returns true if `depth` passes.
+    fn test_and_set(&mut self, x: u32, y: u32, depth: f32) -> bool {
+        let i = self.index(x, y);
+        if depth <= self.values[i] {
+            self.values[i] = depth;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "depth sample ({}, {}) out of bounds", x, y);
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+/// Headless `Renderer` that rasterizes on the CPU into a `Framebuffer`.
+///
+/// Mirrors `vertex_main`/`fragment_main` in `render.metal`: positions are
+/// transformed by the frame's view and projection matrices, clipped, and
+/// the per-vertex color and light are interpolated perspective-correctly
+/// across each triangle. Like the Metal pipeline, both windings are drawn and fragments
+/// are depth tested less-equal against a depth buffer cleared to 1.0.
+pub struct SoftwareRenderer {
+    framebuffer: Framebuffer,
+    depth: DepthBuffer,
+    view_projection: Mat4,
+    uniforms: Uniforms,
+    meshes: Vec<Mesh>,
+    in_frame: bool,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareRenderer {
+            framebuffer: Framebuffer::new(width, height),
+            depth: DepthBuffer::new(width, height),
+            view_projection: Mat4::IDENTITY,
+            uniforms: Uniforms::IDENTITY,
+            meshes: Vec::new(),
+            in_frame: false,
+        }
+    }
+
+    /// The image produced by the last presented (or in-progress) frame.
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    /// The depth target of the last presented (or in-progress) frame.
+    pub fn depth_buffer(&self) -> &DepthBuffer {
+        &self.depth
+    }
+
+    fn draw_triangle(&mut self, vertices: [&Vertex; 3]) {
+        let polygon = clip_polygon(
+            vertices
+                .iter()
+                .map(|v| ClipVertex {
+                    position: self.view_projection.transform(v.position),
+                    varyings: vertex_stage(v),
+                })
+                .collect(),
+        );
+        if polygon.len() < 3 {
+            return;
+        }
+        let screen: Vec<ScreenVertex> = polygon
+            .iter()
+            .map(|v| ScreenVertex::new(v, self.framebuffer.width, self.framebuffer.height))
+            .collect();
+        for i in 1..screen.len() - 1 {
+            self.rasterize(&screen[0], &screen[i], &screen[i + 1]);
+        }
+    }
+
+    fn rasterize(&mut self, v0: &ScreenVertex, v1: &ScreenVertex, v2: &ScreenVertex) {
+        let area = edge(v0.x, v0.y, v1.x, v1.y, v2.x, v2.y);
+        if area == 0.0 {
+            return;
+        }
+        // Normalize the winding so one fill rule covers both windings.
+        let (v1, v2, area) = if area < 0.0 { (v2, v1, -area) } else { (v1, v2, area) };
+
+        let width = self.framebuffer.width as f32;
+        let height = self.framebuffer.height as f32;
+        let min_x = v0.x.min(v1.x).min(v2.x).floor().max(0.0) as u32;
+        let min_y = v0.y.min(v1.y).min(v2.y).floor().max(0.0) as u32;
+        let max_x = v0.x.max(v1.x).max(v2.x).ceil().min(width) as u32;
+        let max_y = v0.y.max(v1.y).max(v2.y).ceil().min(height) as u32;
+
+        let top_left0 = is_top_left(v1, v2);
+        let top_left1 = is_top_left(v2, v0);
+        let top_left2 = is_top_left(v0, v1);
+
+        for y in min_y..max_y {
+            let py = y as f32 + 0.5;
+            for x in min_x..max_x {
+                let px = x as f32 + 0.5;
+                let w0 = edge(v1.x, v1.y, v2.x, v2.y, px, py);
+                let w1 = edge(v2.x, v2.y, v0.x, v0.y, px, py);
+                let w2 = edge(v0.x, v0.y, v1.x, v1.y, px, py);
+                if !covers(w0, top_left0) || !covers(w1, top_left1) || !covers(w2, top_left2) {
+                    continue;
+                }
+                let (b0, b1, b2) = (w0 / area, w1 / area, w2 / area);
+
+                // z/w is affine in screen space, so it interpolates without correction.
+                let depth = b0 * v0.z + b1 * v1.z + b2 * v2.z;
+                if !self.depth.test_and_set(x, y, depth) {
+                    continue;
+                }
+
+                // Perspective-correct interpolation: interpolate attr/w and 1/w linearly.
+                let inv_w = b0 * v0.inv_w + b1 * v1.inv_w + b2 * v2.inv_w;
+                let mut varyings = [0.0; VARYINGS];
+                for (i, value) in varyings.iter_mut().enumerate() {
+                    *value = (b0 * v0.varyings[i] * v0.inv_w
+                        + b1 * v1.varyings[i] * v1.inv_w
+                        + b2 * v2.varyings[i] * v2.inv_w)
+                        / inv_w;
+                }
+                self.framebuffer.set_pixel(x, y, to_rgba8(fragment_stage(&varyings, &self.uniforms)));
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn begin_frame(&mut self, clear_color: [f32; 4], uniforms: &Uniforms) {
+        assert!(!self.in_frame, "begin_frame called twice without present");
+        self.in_frame = true;
+        self.view_projection = uniforms.projection * uniforms.view;
+        self.uniforms = *uniforms;
+        self.framebuffer.clear(to_rgba8(clear_color));
+        self.depth.clear(1.0);
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.framebuffer = Framebuffer::new(width, height);
+        self.depth = DepthBuffer::new(width, height);
+    }
+
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshHandle {
+        self.meshes.push(mesh.clone());
+        MeshHandle(self.meshes.len() - 1)
+    }
+
+    fn draw(&mut self, mesh: MeshHandle) {
+        assert!(self.in_frame, "draw called outside of a frame");
+        let index = mesh.0;
+        let mesh = std::mem::take(&mut self.meshes[index]);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let vertices = [
+                &mesh.vertices[triangle[0] as usize],
+                &mesh.vertices[triangle[1] as usize],
+                &mesh.vertices[triangle[2] as usize],
+            ];
+            self.draw_triangle(vertices);
+        }
+        self.meshes[index] = mesh;
+    }
+
+    fn present(&mut self) {
+        assert!(self.in_frame, "present called outside of a frame");
+        self.in_frame = false;
+    }
+}
+
+/// Values interpolated across a triangle: color (4), face shade, ambient
+/// occlusion, sky light and block light.
+const VARYINGS: usize = 8;
+
+/// The non-positional half of `vertex_main`: what gets interpolated.
+fn vertex_stage(v: &Vertex) -> [f32; VARYINGS] {
+    let [r, g, b, a] = v.color;
+    [r, g, b, a, v.light, v.ao, v.sky_light, v.block_light]
+}
+
+/// `fragment_main`.
+fn fragment_stage(varyings: &[f32; VARYINGS], uniforms: &Uniforms) -> [f32; 4] {
+    let [r, g, b, a, light, ao, sky, block] = *varyings;
+    let shade = light * ao;
+    let lit = |i: usize| (sky * uniforms.sky_light[i]).max(block * uniforms.block_light[i]) * shade;
+    [r * lit(0), g * lit(1), b * lit(2), a]
+}
+
+/// Vertex attributes carried through clipping and interpolation.
+#[derive(Clone, Copy, Debug)]
+struct ClipVertex {
+    position: [f32; 4],
+    varyings: [f32; VARYINGS],
+}
+
+impl ClipVertex {
+    fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            position: lerp(self.position, other.position, t),
+            varyings: lerp(self.varyings, other.varyings, t),
+        }
+    }
+}
+
+/// Clips a polygon against Metal's near (z >= 0) and far (z <= w) planes.
+/// X and Y need no clipping since rasterization is bounded to the framebuffer.
+fn clip_polygon(polygon: Vec<ClipVertex>) -> Vec<ClipVertex> {
+    let near = clip_against(polygon, |p| p[2]);
+    clip_against(near, |p| p[3] - p[2])
+}
+
+/// Sutherland-Hodgman against a single plane; `distance` is positive inside.
+fn clip_against(polygon: Vec<ClipVertex>, distance: impl Fn(&[f32; 4]) -> f32) -> Vec<ClipVertex> {
+    let mut out = Vec::with_capacity(polygon.len() + 1);
+    for (i, current) in polygon.iter().enumerate() {
+        let next = &polygon[(i + 1) % polygon.len()];
+        let d_current = distance(&current.position);
+        let d_next = distance(&next.position);
+        if d_current >= 0.0 {
+            out.push(*current);
+        }
+        if (d_current >= 0.0) != (d_next >= 0.0) {
+            out.push(current.lerp(next, d_current / (d_current - d_next)));
+        }
+    }
+    out
+}
+
+/// A clipped vertex after the perspective divide and viewport transform.
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    varyings: [f32; VARYINGS],
+}
+
+impl ScreenVertex {
+    fn new(v: &ClipVertex, width: u32, height: u32) -> Self {
+        let [x, y, z, w] = v.position;
+        let inv_w = 1.0 / w;
+        ScreenVertex {
+            x: (x * inv_w * 0.5 + 0.5) * width as f32,
+            y: (0.5 - y * inv_w * 0.5) * height as f32,
+            z: z * inv_w,
+            inv_w,
+            varyings: v.varyings,
+        }
+    }
+}
+
+/// Twice the signed area of (a, b, p); positive when p is on the inner side of a->b.
+fn edge(ax: f32, ay: f32, bx: f32, by: f32, px: f32, py: f32) -> f32 {
+    (bx - ax) * (py - ay) - (by - ay) * (px - ax)
+}
+
+/// Top-left fill rule, so pixels on an edge shared by two triangles are drawn once.
+fn is_top_left(a: &ScreenVertex, b: &ScreenVertex) -> bool {
+    let top = a.y == b.y && b.x > a.x;
+    let left = b.y < a.y;
+    top || left
+}
+
+fn covers(w: f32, top_left: bool) -> bool {
+    w > 0.0 || (w == 0.0 && top_left)
+}
+
+fn lerp<const N: usize>(a: [f32; N], b: [f32; N], t: f32) -> [f32; N] {
+    let mut out = a;
+    for (o, b) in out.iter_mut().zip(b) {
+        *o += (b - *o) * t;
+    }
+    out
+}
+
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
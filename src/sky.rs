@@ -0,0 +1,237 @@
+//! Time of day and how it colors the sky.
+//!
+//! A `WorldClock` counts days; the fraction into the current day decides
+//! where the sun and moon are, the clear color and how strongly sky light
+//! shines on the world. Time of day runs from 0.0 (midnight) through 0.25
+//! (sunrise), 0.5 (noon) and 0.75 (sunset) back to midnight.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use crate::math::Vec3;
+
+/// Real seconds in one in-game day by default.
+pub const DEFAULT_DAY_LENGTH: f32 = 1200.0;
+
+pub const MIDNIGHT: f32 = 0.0;
+pub const SUNRISE: f32 = 0.25;
+pub const NOON: f32 = 0.5;
+pub const SUNSET: f32 = 0.75;
+
+/// Keeps the in-game time. It only moves when `advance` is called, and not
+/// while paused, so frames and tests can pin any time of day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldClock {
+    /// Days elapsed since day 0 began at midnight.
+    days: f64,
+    day_length: f32,
+    paused: bool,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        WorldClock::new(DEFAULT_DAY_LENGTH)
+    }
+}
+
+impl WorldClock {
+    /// A running clock starting at sunrise of day 0, with days lasting
+    /// `day_length` real seconds.
+    pub fn new(day_length: f32) -> Self {
+        assert!(day_length > 0.0, "day length must be positive, got {}", day_length);
+        WorldClock { days: SUNRISE as f64, day_length, paused: false }
+    }
+
+    pub fn day_length(&self) -> f32 {
+        self.day_length
+    }
+
+    pub fn set_day_length(&mut self, day_length: f32) {
+        assert!(day_length > 0.0, "day length must be positive, got {}", day_length);
+        self.day_length = day_length;
+    }
+
+    /// Moves the clock on by `elapsed` real time, unless paused.
+    pub fn advance(&mut self, elapsed: Duration) {
+        if !self.paused {
+            self.days += elapsed.as_secs_f64() / self.day_length as f64;
+        }
+    }
+
+    /// Fraction of the current day that has passed, in 0.0..1.0.
+    pub fn time_of_day(&self) -> f32 {
+        self.days.fract() as f32
+    }
+
+    /// Jumps to `time` (wrapped into 0.0..1.0) of the current day.
+    pub fn set_time_of_day(&mut self, time: f32) {
+        self.days = self.days.floor() + time.rem_euclid(1.0) as f64;
+    }
+
+    /// Number of the current day, starting at 0.
+    pub fn day(&self) -> u64 {
+        self.days as u64
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// The sky at the current time of day.
+    pub fn sky(&self) -> Sky {
+        Sky::at(self.time_of_day())
+    }
+}
+
+/// Everything about the sky that depends on the time of day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sky {
+    /// Unit vector towards the sun. It rises in the east (+X), is highest at
+    /// noon and sets in the west, with a slight tilt to the south (+Z).
+    pub sun_direction: Vec3,
+    /// Unit vector towards the moon, opposite the sun.
+    pub moon_direction: Vec3,
+    /// Color of the sky, used as the clear color.
+    pub color: [f32; 3],
+    /// Multiplier applied to sky light in the shader: white at noon, dim
+    /// blue at night.
+    pub light: [f32; 3],
+}
+
+/// Sky color keyframes over the day.
+const SKY_COLORS: [(f32, [f32; 3]); 8] = [
+    (0.0, [0.01, 0.01, 0.05]),
+    (0.21, [0.02, 0.02, 0.08]),
+    (0.25, [0.85, 0.50, 0.32]),
+    (0.30, [0.50, 0.70, 1.00]),
+    (0.70, [0.50, 0.70, 1.00]),
+    (0.75, [0.90, 0.45, 0.25]),
+    (0.79, [0.02, 0.02, 0.08]),
+    (1.0, [0.01, 0.01, 0.05]),
+];
+
+/// Sky light multiplier keyframes over the day.
+const SKY_LIGHT: [(f32, [f32; 3]); 8] = [
+    (0.0, [0.14, 0.16, 0.26]),
+    (0.21, [0.14, 0.16, 0.26]),
+    (0.25, [0.75, 0.60, 0.50]),
+    (0.30, [1.00, 1.00, 1.00]),
+    (0.70, [1.00, 1.00, 1.00]),
+    (0.75, [0.75, 0.55, 0.45]),
+    (0.79, [0.14, 0.16, 0.26]),
+    (1.0, [0.14, 0.16, 0.26]),
+];
+
+/// Smoothly interpolates between the keyframes around `time`.
+fn gradient(keys: &[(f32, [f32; 3])], time: f32) -> [f32; 3] {
+    let next = keys.iter().position(|&(at, _)| at > time).unwrap_or(keys.len() - 1).max(1);
+    let ((from, a), (to, b)) = (keys[next - 1], keys[next]);
+    let t = ((time - from) / (to - from)).clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+impl Sky {
+    /// The sky at `time` of day (wrapped into 0.0..1.0).
+    pub fn at(time: f32) -> Self {
+        let time = time.rem_euclid(1.0);
+        let (sin, cos) = ((time - SUNRISE) * TAU).sin_cos();
+        let sun_direction = Vec3::new(cos, sin, 0.25 * sin.abs()).normalize();
+        Sky {
+            sun_direction,
+            moon_direction: -sun_direction,
+            color: gradient(&SKY_COLORS, time),
+            light: gradient(&SKY_LIGHT, time),
+        }
+    }
+
+    /// `color` as an opaque clear color.
+    pub fn clear_color(&self) -> [f32; 4] {
+        let [r, g, b] = self.color;
+        [r, g, b, 1.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(clock: &WorldClock, days: f32) -> Duration {
+        Duration::from_secs_f32(clock.day_length() * days)
+    }
+
+    #[test]
+    fn paused_clock_stands_still() {
+        let mut clock = WorldClock::new(10.0);
+        clock.pause();
+        clock.advance(days(&clock, 0.4));
+        assert_eq!((clock.day(), clock.time_of_day()), (0, SUNRISE));
+        clock.resume();
+        clock.advance(days(&clock, 0.25));
+        assert_eq!((clock.day(), clock.time_of_day()), (0, NOON));
+    }
+
+    #[test]
+    fn a_full_day_keeps_the_time_of_day() {
+        let mut clock = WorldClock::new(10.0);
+        clock.advance(days(&clock, 1.0));
+        assert_eq!((clock.day(), clock.time_of_day()), (1, SUNRISE));
+        clock.advance(days(&clock, 0.8));
+        assert_eq!(clock.day(), 2);
+        assert!((clock.time_of_day() - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn set_time_of_day_wraps_within_the_day() {
+        let mut clock = WorldClock::new(10.0);
+        clock.advance(days(&clock, 3.0));
+        for (time, wrapped) in [(1.25, 0.25), (-0.75, 0.25), (-0.25, 0.75), (2.0, 0.0)] {
+            clock.set_time_of_day(time);
+            assert_eq!((clock.day(), clock.time_of_day()), (3, wrapped), "{}", time);
+        }
+    }
+
+    #[test]
+    fn sun_crosses_the_sky() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+        let sunrise = Sky::at(SUNRISE).sun_direction;
+        assert!(close(sunrise.x, 1.0) && close(sunrise.y, 0.0) && close(sunrise.z, 0.0), "{:?}", sunrise);
+        // Straight up but for the tilt to the south.
+        let noon = Sky::at(NOON).sun_direction;
+        assert!(close(noon.x, 0.0) && noon.y > 0.95 && noon.z > 0.0, "{:?}", noon);
+        assert!(Sky::at(SUNSET).sun_direction.x < -0.99);
+        let midnight = Sky::at(MIDNIGHT).sun_direction;
+        assert!(midnight.y < -0.95, "{:?}", midnight);
+
+        for time in [0.0, 0.1, SUNRISE, 0.4, NOON, 0.9] {
+            let sky = Sky::at(time);
+            assert_eq!(sky.moon_direction, -sky.sun_direction);
+            assert!(close(sky.sun_direction.length(), 1.0));
+        }
+    }
+
+    #[test]
+    fn gradient_hits_its_keyframes() {
+        for keys in [&SKY_COLORS, &SKY_LIGHT] {
+            for &(time, color) in keys.iter() {
+                assert_eq!(gradient(keys, time), color, "{}", time);
+            }
+        }
+        // Halfway between two keyframes, smoothstep is halfway too.
+        let [r, g, b] = gradient(&SKY_COLORS, 0.275);
+        assert!((r - 0.675).abs() < 1e-6 && (g - 0.6).abs() < 1e-6 && (b - 0.66).abs() < 1e-6);
+        assert_eq!(Sky::at(1.0), Sky::at(0.0));
+    }
+}
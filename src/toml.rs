@@ -0,0 +1,290 @@
+//! Just enough TOML for the game's data files (`blocks.toml`,
+//! `bindings.toml`): `key = value` lines, optionally grouped under
+//! `[[table]]` headers, where a value is a string, integer, float, boolean or
+//! a single-line array of those. Comments start with `#`.
+
+/// Malformed file; `line` is 1-based.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        ParseError { line, message: message.into() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "boolean",
+            Value::Array(_) => "array",
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Integer(i) => Some(i as f64),
+            Value::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+}
+
+/// One `[[name]]` table and its keys, with line numbers for error messages.
+#[derive(Debug)]
+pub(crate) struct Table {
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Entry {
+    pub line: usize,
+    pub key: String,
+    pub value: Value,
+}
+
+/// Parses every `[[table_name]]` table in `source`.
+pub(crate) fn parse_tables(source: &str, table_name: &str) -> Result<Vec<Table>, ParseError> {
+    let mut tables: Vec<Table> = Vec::new();
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        let text = strip_comment(raw).trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = text.strip_prefix("[[").and_then(|t| t.strip_suffix("]]")) {
+            if header.trim() != table_name {
+                return Err(ParseError::new(line, format!("unknown table [[{}]]", header.trim())));
+            }
+            tables.push(Table { line, entries: Vec::new() });
+            continue;
+        }
+
+        let entry = parse_entry(line, text)?;
+        let table = tables.last_mut().ok_or_else(|| {
+            ParseError::new(line, format!("`{}` outside of a [[{}]] table", entry.key, table_name))
+        })?;
+        push_entry(&mut table.entries, entry)?;
+    }
+    Ok(tables)
+}
+
+/// Parses a file of top-level `key = value` lines without tables.
+pub(crate) fn parse_entries(source: &str) -> Result<Vec<Entry>, ParseError> {
+    let mut entries = Vec::new();
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        let text = strip_comment(raw).trim();
+        if text.is_empty() {
+            continue;
+        }
+        if text.starts_with('[') {
+            return Err(ParseError::new(line, format!("unexpected table header `{}`", text)));
+        }
+        push_entry(&mut entries, parse_entry(line, text)?)?;
+    }
+    Ok(entries)
+}
+
+fn push_entry(entries: &mut Vec<Entry>, entry: Entry) -> Result<(), ParseError> {
+    if entries.iter().any(|e| e.key == entry.key) {
+        return Err(ParseError::new(entry.line, format!("duplicate key `{}`", entry.key)));
+    }
+    entries.push(entry);
+    Ok(())
+}
+
+/// Parses one `key = value` line.
+fn parse_entry(line: usize, text: &str) -> Result<Entry, ParseError> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| ParseError::new(line, "expected `key = value`"))?;
+    let key = key.trim();
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(ParseError::new(line, format!("invalid key `{}`", key)));
+    }
+    let (value, rest) = parse_value(value.trim()).map_err(|message| ParseError::new(line, message))?;
+    if !rest.trim().is_empty() {
+        return Err(ParseError::new(line, format!("unexpected `{}` after value", rest.trim())));
+    }
+    Ok(Entry { line, key: key.to_owned(), value })
+}
+
+/// Removes a trailing `#` comment that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parses one value from the start of `text`, returning it and the unparsed remainder.
+fn parse_value(text: &str) -> Result<(Value, &str), String> {
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => match chars.next() {
+                    Some((_, '"')) => value.push('"'),
+                    Some((_, '\\')) => value.push('\\'),
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c)) => return Err(format!("unsupported escape `\\{}`", c)),
+                    None => break,
+                },
+                _ => value.push(c),
+            }
+        }
+        return Err("unterminated string".to_owned());
+    }
+
+    if let Some(mut rest) = text.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(items), after));
+            }
+            let (item, after) = parse_value(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err("expected `,` or `]` in array".to_owned());
+            }
+        }
+    }
+
+    let end = text.find([',', ']', ' ', '\t']).unwrap_or(text.len());
+    let (token, rest) = text.split_at(end);
+    let value = match token {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => {
+            let digits = token.replace('_', "");
+            if let Ok(i) = digits.parse::<i64>() {
+                Value::Integer(i)
+            } else if let Some(f) = digits.parse::<f64>().ok().filter(|f| f.is_finite()) {
+                // Rust also parses `nan` and `inf`, which no data file means.
+                Value::Float(f)
+            } else {
+                return Err(format!("invalid value `{}`", token));
+            }
+        }
+    };
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> Value {
+        let entries = parse_entries(&format!("key = {}", text)).unwrap();
+        entries.into_iter().next().unwrap().value
+    }
+
+    fn error(source: &str) -> (usize, String) {
+        let e = parse_entries(source).unwrap_err();
+        (e.line, e.message)
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(value("true"), Value::Bool(true));
+        assert_eq!(value("-42"), Value::Integer(-42));
+        assert_eq!(value("1_000_000"), Value::Integer(1_000_000));
+        assert_eq!(value("0.25"), Value::Float(0.25));
+        assert_eq!(value("1_0.5e-1"), Value::Float(1.05));
+    }
+
+    #[test]
+    fn non_finite_floats_are_rejected() {
+        for text in ["nan", "NaN", "inf", "-inf", "infinity", "1e999"] {
+            assert_eq!(error(&format!("key = {}", text)), (1, format!("invalid value `{}`", text)));
+        }
+    }
+
+    #[test]
+    fn strings_with_escapes() {
+        assert_eq!(value(r#""a \"b\" \\ c\n\t""#), Value::String("a \"b\" \\ c\n\t".to_owned()));
+        assert_eq!(value("\"# not a comment\""), Value::String("# not a comment".to_owned()));
+        assert_eq!(error(r#"key = "\x""#).1, "unsupported escape `\\x`");
+        assert_eq!(error(r#"key = "open"#).1, "unterminated string");
+        assert_eq!(error(r#"key = "\"#).1, "unterminated string");
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(value("[]"), Value::Array(Vec::new()));
+        assert_eq!(
+            value(r#"[1, "two" , [3.5, false],]"#),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::String("two".to_owned()),
+                Value::Array(vec![Value::Float(3.5), Value::Bool(false)]),
+            ])
+        );
+        assert_eq!(error("key = [1 2]").1, "expected `,` or `]` in array");
+        assert_eq!(error("key = [1, 2").1, "expected `,` or `]` in array");
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let entries = parse_entries("# header\n\n  a = 1 # trailing\nb = \"x#y\" # more\n").unwrap();
+        let pairs: Vec<_> = entries.iter().map(|e| (e.line, e.key.as_str(), &e.value)).collect();
+        assert_eq!(pairs, [(3, "a", &Value::Integer(1)), (4, "b", &Value::String("x#y".to_owned()))]);
+    }
+
+    #[test]
+    fn malformed_lines() {
+        assert_eq!(error("a = 1\njust words"), (2, "expected `key = value`".to_owned()));
+        assert_eq!(error("a b = 1"), (1, "invalid key `a b`".to_owned()));
+        assert_eq!(error(" = 1"), (1, "invalid key ``".to_owned()));
+        assert_eq!(error("a = 1 2"), (1, "unexpected `2` after value".to_owned()));
+        assert_eq!(error("a = yes"), (1, "invalid value `yes`".to_owned()));
+        assert_eq!(error("a = 1\na = 2"), (2, "duplicate key `a`".to_owned()));
+        assert_eq!(error("[[block]]"), (1, "unexpected table header `[[block]]`".to_owned()));
+    }
+
+    #[test]
+    fn tables() {
+        let tables = parse_tables("[[block]]\nid = 0\n\n[[block]]\nid = 1\nname = \"stone\"\n", "block").unwrap();
+        assert_eq!(tables.iter().map(|t| (t.line, t.entries.len())).collect::<Vec<_>>(), [(1, 1), (4, 2)]);
+        // The same key may appear once per table.
+        assert_eq!(tables[1].entries[0].value, Value::Integer(1));
+
+        let e = parse_tables("id = 0\n", "block").unwrap_err();
+        assert_eq!((e.line, e.message.as_str()), (1, "`id` outside of a [[block]] table"));
+        let e = parse_tables("[[block]]\n[[item]]\n", "block").unwrap_err();
+        assert_eq!((e.line, e.message.as_str()), (2, "unknown table [[item]]"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Typed vertex layouts.
+//!
+//! A `#[repr(C)]` vertex struct implements `VertexFormat` (normally through
+//! `vertex_format!`), which lists the shader attribute each field feeds. The
+//! Metal vertex descriptor is generated from that list, and
+//! `check_shader_inputs` verifies it against the `[[attribute(n)]]` fields the
+//! shader declares, so the struct, descriptor and shader cannot drift apart.
+
+use std::fmt;
+use std::mem::size_of;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeFormat {
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    /// Four bytes mapped to 0.0..=1.0 in the shader.
+    UChar4Normalized,
+}
+
+impl AttributeFormat {
+    /// Size of the field in the vertex buffer, in bytes.
+    pub const fn size(self) -> usize {
+        match self {
+            AttributeFormat::Float => 4,
+            AttributeFormat::Float2 => 8,
+            AttributeFormat::Float3 => 12,
+            AttributeFormat::Float4 => 16,
+            AttributeFormat::UChar4Normalized => 4,
+        }
+    }
+
+    /// Whether a shader input declared as `shader_type` can read this format.
+    pub fn matches_shader_type(self, shader_type: &str) -> bool {
+        match self {
+            AttributeFormat::Float => matches!(shader_type, "float" | "half"),
+            AttributeFormat::Float2 => matches!(shader_type, "float2" | "half2"),
+            AttributeFormat::Float3 => matches!(shader_type, "float3" | "half3" | "packed_float3"),
+            AttributeFormat::Float4 | AttributeFormat::UChar4Normalized => {
+                matches!(shader_type, "float4" | "half4")
+            }
+        }
+    }
+}
+
+/// One field of a vertex struct as seen by the shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+    /// The `[[attribute(n)]]` index in the shader.
+    pub index: usize,
+    pub name: &'static str,
+    pub format: AttributeFormat,
+    /// Byte offset of the field within the struct.
+    pub offset: usize,
+}
+
+/// A vertex type that can be uploaded as-is into a vertex buffer.
+pub trait VertexFormat: Copy + 'static {
+    const ATTRIBUTES: &'static [VertexAttribute];
+
+    fn stride() -> usize {
+        size_of::<Self>()
+    }
+}
+
+/// Implements `VertexFormat` for a `#[repr(C)]` struct.
+///
+/// ```
+/// use metalcraft::vertex::{AttributeFormat, VertexFormat};
+/// use metalcraft::vertex_format;
+///
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct Vertex {
+///     position: [f32; 4],
+///     color: [f32; 4],
+/// }
+///
+/// vertex_format!(Vertex {
+///     0 => position: Float4,
+///     1 => color: Float4,
+/// });
+///
+/// assert_eq!(Vertex::ATTRIBUTES[1].offset, 16);
+/// assert_eq!(Vertex::ATTRIBUTES[1].format, AttributeFormat::Float4);
+/// ```
+///
+/// Offsets come from `offset_of!`, and a field whose size does not match its
+/// declared format is rejected at compile time.
+#[macro_export]
+macro_rules! vertex_format {
+    ($ty:ident { $($index:literal => $field:ident : $format:ident),* $(,)? }) => {
+        impl $crate::vertex::VertexFormat for $ty {
+            const ATTRIBUTES: &'static [$crate::vertex::VertexAttribute] = &[
+                $($crate::vertex::VertexAttribute {
+                    index: $index,
+                    name: stringify!($field),
+                    format: $crate::vertex::AttributeFormat::$format,
+                    offset: ::std::mem::offset_of!($ty, $field),
+                },)*
+            ];
+        }
+
+        const _: () = {
+            // Never called; `transmute` refuses to compile if the sizes differ.
+            #[allow(dead_code, unknown_lints, unnecessary_transmutes, clippy::missing_transmute_annotations)]
+            fn check_field_sizes(vertex: $ty) {
+                $(let _: [u8; $crate::vertex::AttributeFormat::$format.size()] =
+                    unsafe { ::std::mem::transmute(vertex.$field) };)*
+            }
+        };
+    };
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VertexLayoutError {
+    /// The shader source has no `struct <name>`.
+    MissingStruct(String),
+    /// The shader reads an attribute index the vertex type does not provide.
+    MissingAttribute { index: usize, shader_field: String },
+    /// Both sides have the attribute but disagree on its type.
+    TypeMismatch { index: usize, shader_type: String, format: AttributeFormat },
+    /// Two attributes overlap or run past the end of the vertex.
+    BadOffset { name: &'static str },
+}
+
+impl fmt::Display for VertexLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VertexLayoutError::MissingStruct(name) => write!(f, "shader has no struct {}", name),
+            VertexLayoutError::MissingAttribute { index, shader_field } => write!(
+                f,
+                "shader field `{}` reads attribute({}), which the vertex type does not provide",
+                shader_field, index
+            ),
+            VertexLayoutError::TypeMismatch { index, shader_type, format } => write!(
+                f,
+                "attribute({}) is {:?} in the vertex type but `{}` in the shader",
+                index, format, shader_type
+            ),
+            VertexLayoutError::BadOffset { name } => {
+                write!(f, "vertex field `{}` overlaps another field or the end of the vertex", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VertexLayoutError {}
+
+/// Checks that `V`'s attributes are disjoint and fit inside its stride.
+pub fn validate_layout<V: VertexFormat>() -> Result<(), VertexLayoutError> {
+    let mut ranges: Vec<_> = V::ATTRIBUTES
+        .iter()
+        .map(|a| (a.offset, a.offset + a.format.size(), a.name))
+        .collect();
+    ranges.sort();
+    for (i, &(_, end, name)) in ranges.iter().enumerate() {
+        let next_start = ranges.get(i + 1).map_or(V::stride(), |r| r.0);
+        if end > next_start {
+            return Err(VertexLayoutError::BadOffset { name });
+        }
+    }
+    Ok(())
+}
+
+/// Checks `V` against the `[[attribute(n)]]` fields of `struct_name` in a Metal shader.
+pub fn check_shader_inputs<V: VertexFormat>(shader_source: &str, struct_name: &str) -> Result<(), VertexLayoutError> {
+    validate_layout::<V>()?;
+    for (index, shader_type, shader_field) in shader_attributes(shader_source, struct_name)? {
+        let attribute = V::ATTRIBUTES
+            .iter()
+            .find(|a| a.index == index)
+            .ok_or_else(|| VertexLayoutError::MissingAttribute { index, shader_field: shader_field.clone() })?;
+        if !attribute.format.matches_shader_type(&shader_type) {
+            return Err(VertexLayoutError::TypeMismatch { index, shader_type, format: attribute.format });
+        }
+    }
+    Ok(())
+}
+
+/// Extracts `(index, type, field)` for each `type field [[attribute(index)]];` in the struct.
+fn shader_attributes(source: &str, struct_name: &str) -> Result<Vec<(usize, String, String)>, VertexLayoutError> {
+    let missing = || VertexLayoutError::MissingStruct(struct_name.to_owned());
+    let header = format!("struct {}", struct_name);
+    let start = source
+        .match_indices(&header)
+        .map(|(i, _)| i + header.len())
+        .find(|&i| source[i..].trim_start().starts_with('{'))
+        .ok_or_else(missing)?;
+    let body = &source[start..];
+    let body = &body[body.find('{').unwrap() + 1..body.find('}').ok_or_else(missing)?];
+
+    let mut attributes = Vec::new();
+    for declaration in body.split(';') {
+        let Some(attr_start) = declaration.find("[[attribute(") else { continue };
+        let after = &declaration[attr_start + "[[attribute(".len()..];
+        let Some(index) = after.split(')').next().and_then(|n| n.trim().parse().ok()) else { continue };
+        let mut words = declaration[..attr_start].split_whitespace();
+        if let (Some(ty), Some(field)) = (words.next(), words.next()) {
+            attributes.push((index, ty.to_owned(), field.to_owned()));
+        }
+    }
+    Ok(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::Vertex;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Plain {
+        position: [f32; 4],
+        uv: [f32; 2],
+    }
+
+    vertex_format!(Plain {
+        0 => position: Float4,
+        1 => uv: Float2,
+    });
+
+    const PLAIN_SHADER: &str = "
+        struct PlainIn {
+            float4 position [[attribute(0)]];
+            float2 uv [[attribute(1)]];
+        };
+    ";
+
+    #[test]
+    fn mesh_vertex_matches_the_shader() {
+        assert_eq!(check_shader_inputs::<Vertex>(include_str!("render.metal"), "VertexIn"), Ok(()));
+    }
+
+    #[test]
+    fn matching_struct_passes() {
+        assert_eq!(check_shader_inputs::<Plain>(PLAIN_SHADER, "PlainIn"), Ok(()));
+    }
+
+    #[test]
+    fn missing_struct() {
+        assert_eq!(
+            check_shader_inputs::<Plain>(PLAIN_SHADER, "VertexIn"),
+            Err(VertexLayoutError::MissingStruct("VertexIn".to_owned()))
+        );
+    }
+
+    #[test]
+    fn missing_attribute() {
+        let shader = PLAIN_SHADER.replace("};", "float3 normal [[attribute(2)]]; };");
+        assert_eq!(
+            check_shader_inputs::<Plain>(&shader, "PlainIn"),
+            Err(VertexLayoutError::MissingAttribute { index: 2, shader_field: "normal".to_owned() })
+        );
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let shader = PLAIN_SHADER.replace("float2 uv", "float3 uv");
+        assert_eq!(
+            check_shader_inputs::<Plain>(&shader, "PlainIn"),
+            Err(VertexLayoutError::TypeMismatch {
+                index: 1,
+                shader_type: "float3".to_owned(),
+                format: AttributeFormat::Float2,
+            })
+        );
+    }
+
+    #[test]
+    fn overlapping_offsets() {
+        #[derive(Clone, Copy)]
+        struct Overlapping {
+            _data: [f32; 4],
+        }
+
+        impl VertexFormat for Overlapping {
+            const ATTRIBUTES: &'static [VertexAttribute] = &[
+                VertexAttribute { index: 0, name: "position", format: AttributeFormat::Float3, offset: 0 },
+                VertexAttribute { index: 1, name: "uv", format: AttributeFormat::Float2, offset: 8 },
+            ];
+        }
+
+        assert_eq!(validate_layout::<Overlapping>(), Err(VertexLayoutError::BadOffset { name: "position" }));
+    }
+
+    #[test]
+    fn attribute_past_the_end() {
+        #[derive(Clone, Copy)]
+        struct Short {
+            _data: [f32; 2],
+        }
+
+        impl VertexFormat for Short {
+            const ATTRIBUTES: &'static [VertexAttribute] =
+                &[VertexAttribute { index: 0, name: "position", format: AttributeFormat::Float3, offset: 0 }];
+        }
+
+        assert_eq!(validate_layout::<Short>(), Err(VertexLayoutError::BadOffset { name: "position" }));
+    }
+}
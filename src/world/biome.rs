@@ -0,0 +1,176 @@
+//! Biomes: per-column climate zones that shape the terrain and tint grass,
+//! leaves and water.
+
+/// Which biome color a block takes instead of its flat `color`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tint {
+    #[default]
+    None,
+    Grass,
+    Foliage,
+    Water,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Biome {
+    #[default]
+    Plains,
+    Forest,
+    Desert,
+    Savanna,
+    Swamp,
+    Taiga,
+    Tundra,
+    Mountains,
+}
+
+/// Static properties of a biome.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BiomeDef {
+    pub name: &'static str,
+    /// Position in climate space, both in -1.0..1.0; columns get the biomes
+    /// whose climate is closest to theirs.
+    pub temperature: f64,
+    pub humidity: f64,
+    /// Added to the base terrain height, in blocks.
+    pub height_offset: f64,
+    /// Multiplier for the height of hills.
+    pub height_scale: f64,
+    /// Block names of the top layer and the layers below it.
+    pub surface: &'static str,
+    pub soil: &'static str,
+    /// Chance per column of growing a tree.
+    pub foliage_density: f32,
+    pub grass_tint: [f32; 3],
+    pub foliage_tint: [f32; 3],
+    pub water_tint: [f32; 3],
+}
+
+const BIOMES: [BiomeDef; 8] = [
+    BiomeDef {
+        name: "plains",
+        temperature: 0.2,
+        humidity: -0.2,
+        height_offset: 0.0,
+        height_scale: 0.6,
+        surface: "grass",
+        soil: "dirt",
+        foliage_density: 0.002,
+        grass_tint: [0.36, 0.62, 0.26],
+        foliage_tint: [0.22, 0.50, 0.16],
+        water_tint: [0.20, 0.35, 0.80],
+    },
+    BiomeDef {
+        name: "forest",
+        temperature: 0.1,
+        humidity: 0.4,
+        height_offset: 2.0,
+        height_scale: 1.0,
+        surface: "grass",
+        soil: "dirt",
+        foliage_density: 0.04,
+        grass_tint: [0.30, 0.56, 0.22],
+        foliage_tint: [0.18, 0.44, 0.12],
+        water_tint: [0.18, 0.33, 0.76],
+    },
+    BiomeDef {
+        name: "desert",
+        temperature: 0.9,
+        humidity: -0.7,
+        height_offset: 1.0,
+        height_scale: 0.4,
+        surface: "sand",
+        soil: "sand",
+        foliage_density: 0.0,
+        grass_tint: [0.75, 0.72, 0.40],
+        foliage_tint: [0.68, 0.65, 0.32],
+        water_tint: [0.26, 0.50, 0.78],
+    },
+    BiomeDef {
+        name: "savanna",
+        temperature: 0.7,
+        humidity: -0.1,
+        height_offset: 1.0,
+        height_scale: 0.5,
+        surface: "grass",
+        soil: "dirt",
+        foliage_density: 0.006,
+        grass_tint: [0.62, 0.66, 0.30],
+        foliage_tint: [0.52, 0.58, 0.22],
+        water_tint: [0.24, 0.42, 0.76],
+    },
+    BiomeDef {
+        name: "swamp",
+        temperature: 0.5,
+        humidity: 0.85,
+        height_offset: -3.0,
+        height_scale: 0.2,
+        surface: "grass",
+        soil: "dirt",
+        foliage_density: 0.02,
+        grass_tint: [0.40, 0.46, 0.22],
+        foliage_tint: [0.32, 0.40, 0.14],
+        water_tint: [0.26, 0.32, 0.28],
+    },
+    BiomeDef {
+        name: "taiga",
+        temperature: -0.5,
+        humidity: 0.3,
+        height_offset: 3.0,
+        height_scale: 1.0,
+        surface: "grass",
+        soil: "dirt",
+        foliage_density: 0.03,
+        grass_tint: [0.38, 0.56, 0.42],
+        foliage_tint: [0.24, 0.42, 0.30],
+        water_tint: [0.16, 0.28, 0.66],
+    },
+    BiomeDef {
+        name: "tundra",
+        temperature: -0.85,
+        humidity: -0.4,
+        height_offset: 1.0,
+        height_scale: 0.5,
+        surface: "snow",
+        soil: "dirt",
+        foliage_density: 0.001,
+        grass_tint: [0.55, 0.68, 0.60],
+        foliage_tint: [0.40, 0.55, 0.45],
+        water_tint: [0.22, 0.30, 0.60],
+    },
+    BiomeDef {
+        name: "mountains",
+        temperature: -0.3,
+        humidity: -0.6,
+        height_offset: 14.0,
+        height_scale: 2.8,
+        surface: "stone",
+        soil: "stone",
+        foliage_density: 0.004,
+        grass_tint: [0.42, 0.58, 0.38],
+        foliage_tint: [0.28, 0.46, 0.24],
+        water_tint: [0.18, 0.32, 0.70],
+    },
+];
+
+impl Biome {
+    pub const ALL: [Biome; 8] = [
+        Biome::Plains,
+        Biome::Forest,
+        Biome::Desert,
+        Biome::Savanna,
+        Biome::Swamp,
+        Biome::Taiga,
+        Biome::Tundra,
+        Biome::Mountains,
+    ];
+
+    pub fn def(self) -> &'static BiomeDef {
+        &BIOMES[self as usize]
+    }
+
+    pub fn name(self) -> &'static str {
+        self.def().name
+    }
+}
@@ -0,0 +1,316 @@
+use std::mem::size_of;
+
+use crate::block::BlockId;
+use crate::math::IVec3;
+use super::biome::Biome;
+use super::light::{LightChannel, LightSection};
+use super::palette::PalettedContainer;
+
+/// Width of a chunk along X and Z, and the edge length of a section.
+pub const CHUNK_SIZE: usize = 16;
+/// Sections stacked vertically in one chunk column.
+pub const SECTION_COUNT: usize = 16;
+pub const CHUNK_HEIGHT: usize = CHUNK_SIZE * SECTION_COUNT;
+const SECTION_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Horizontal position of a chunk column, in chunks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        ChunkPos { x, z }
+    }
+
+    /// The chunk containing world block position `pos`.
+    pub fn containing(pos: IVec3) -> Self {
+        let size = CHUNK_SIZE as i32;
+        ChunkPos::new(pos.x.div_euclid(size), pos.z.div_euclid(size))
+    }
+
+    /// World position of the chunk's block (0, 0, 0).
+    pub fn origin(self) -> IVec3 {
+        let size = CHUNK_SIZE as i32;
+        IVec3::new(self.x * size, 0, self.z * size)
+    }
+
+    pub fn offset(self, dx: i32, dz: i32) -> Self {
+        ChunkPos::new(self.x + dx, self.z + dz)
+    }
+}
+
+/// A 16 x 256 x 16 column of blocks, stored as 16 palette-compressed
+/// 16^3 sections. Coordinates are local: x and z in 0..16, y in 0..256.
+/// Each column also records its biome, and each block its sky and block
+/// light, which start out dark until the light engine has run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    pos: ChunkPos,
+    sections: Vec<PalettedContainer>,
+    light: Vec<LightSection>,
+    /// Indexed by `z * CHUNK_SIZE + x`.
+    biomes: Vec<Biome>,
+}
+
+impl Chunk {
+    /// An all-air chunk of plains.
+    pub fn new(pos: ChunkPos) -> Self {
+        Chunk {
+            pos,
+            sections: vec![PalettedContainer::new(SECTION_VOLUME, BlockId::AIR); SECTION_COUNT],
+            light: vec![LightSection::new(SECTION_VOLUME); SECTION_COUNT],
+            biomes: vec![Biome::default(); CHUNK_SIZE * CHUNK_SIZE],
+        }
+    }
+
+    pub fn pos(&self) -> ChunkPos {
+        self.pos
+    }
+
+    /// True if local coordinates (which may be negative) fall inside the chunk.
+    pub fn contains(x: i32, y: i32, z: i32) -> bool {
+        (0..CHUNK_SIZE as i32).contains(&x)
+            && (0..CHUNK_HEIGHT as i32).contains(&y)
+            && (0..CHUNK_SIZE as i32).contains(&z)
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockId {
+        let (section, index) = Self::locate(x, y, z);
+        self.sections[section].get(index)
+    }
+
+    /// Stores `block` and returns the block that was there before.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockId) -> BlockId {
+        let (section, index) = Self::locate(x, y, z);
+        self.sections[section].set(index, block)
+    }
+
+    /// Light level of `channel` at a block, 0..=15.
+    pub fn light(&self, x: usize, y: usize, z: usize, channel: LightChannel) -> u8 {
+        let (section, index) = Self::locate(x, y, z);
+        self.light[section].get(channel, index)
+    }
+
+    pub fn set_light(&mut self, x: usize, y: usize, z: usize, channel: LightChannel, level: u8) {
+        let (section, index) = Self::locate(x, y, z);
+        self.light[section].set(channel, index, level);
+    }
+
+    /// Sets both light channels of every block.
+    pub fn fill_light(&mut self, sky: u8, block: u8) {
+        for section in &mut self.light {
+            section.fill(sky, block);
+        }
+    }
+
+    /// Sets both light channels of every block in section `index`.
+    pub fn fill_section_light(&mut self, index: usize, sky: u8, block: u8) {
+        self.light[index].fill(sky, block);
+    }
+
+    pub fn biome(&self, x: usize, z: usize) -> Biome {
+        assert!(x < CHUNK_SIZE && z < CHUNK_SIZE, "column ({}, {}) outside of chunk", x, z);
+        self.biomes[z * CHUNK_SIZE + x]
+    }
+
+    pub fn set_biome(&mut self, x: usize, z: usize, biome: Biome) {
+        assert!(x < CHUNK_SIZE && z < CHUNK_SIZE, "column ({}, {}) outside of chunk", x, z);
+        self.biomes[z * CHUNK_SIZE + x] = biome;
+    }
+
+    /// Sets every block in the chunk to `block`.
+    pub fn fill(&mut self, block: BlockId) {
+        for section in &mut self.sections {
+            section.fill(block);
+        }
+    }
+
+    /// Fills the box `min..=max` (local coordinates). Whole sections inside the
+    /// box are reset in O(1) rather than written block by block.
+    pub fn fill_box(&mut self, min: IVec3, max: IVec3, block: BlockId) {
+        let (min, max) = (min.min_elem(max), min.max_elem(max));
+        assert!(
+            Self::contains(min.x, min.y, min.z) && Self::contains(max.x, max.y, max.z),
+            "fill_box({:?}, {:?}) leaves the chunk",
+            min,
+            max
+        );
+        let full_columns = min.x == 0 && min.z == 0 && max.x == CHUNK_SIZE as i32 - 1 && max.z == CHUNK_SIZE as i32 - 1;
+        let size = CHUNK_SIZE as i32;
+        let mut y = min.y;
+        while y <= max.y {
+            let section = (y / size) as usize;
+            let section_top = (section as i32 + 1) * size - 1;
+            if full_columns && y % size == 0 && section_top <= max.y {
+                self.sections[section].fill(block);
+                y += size;
+                continue;
+            }
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    self.set(x as usize, y as usize, z as usize, block);
+                }
+            }
+            y += 1;
+        }
+    }
+
+    /// Blocks that are not air, with their local positions, bottom to top.
+    /// All-air sections are skipped without being scanned.
+    pub fn iter_non_air(&self) -> impl Iterator<Item = (IVec3, BlockId)> + '_ {
+        self.sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| section.uniform_value() != Some(BlockId::AIR))
+            .flat_map(|(s, section)| {
+                section.iter().enumerate().filter(|(_, block)| !block.is_air()).map(move |(i, block)| {
+                    let x = i % CHUNK_SIZE;
+                    let z = (i / CHUNK_SIZE) % CHUNK_SIZE;
+                    let y = i / (CHUNK_SIZE * CHUNK_SIZE) + s * CHUNK_SIZE;
+                    (IVec3::new(x as i32, y as i32, z as i32), block)
+                })
+            })
+    }
+
+    pub fn non_air_count(&self) -> usize {
+        self.sections
+            .iter()
+            .map(|section| SECTION_VOLUME - section.count(BlockId::AIR))
+            .sum()
+    }
+
+    /// True if section `index` (0 = bottom) contains only air.
+    pub fn is_section_empty(&self, index: usize) -> bool {
+        self.sections[index].uniform_value() == Some(BlockId::AIR)
+    }
+
+    /// FNV-1a hash of every block, its light and every column biome in the
+    /// chunk, stable across runs and platforms, for comparing generated
+    /// chunks. Unlike `==` it ignores how the data happens to be stored.
+    pub fn content_hash(&self) -> u64 {
+        let blocks = self.sections.iter().flat_map(PalettedContainer::iter).flat_map(|block| block.0.to_le_bytes());
+        let light = self.light.iter().flat_map(LightSection::iter_packed);
+        let biomes = self.biomes.iter().map(|&biome| biome as u8);
+        blocks.chain(light).chain(biomes).fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    /// Approximate bytes used by the chunk, including its sections and light.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + self.sections.iter().map(PalettedContainer::memory_usage).sum::<usize>()
+            + self.light.iter().map(LightSection::memory_usage).sum::<usize>()
+            + self.biomes.len() * size_of::<Biome>()
+    }
+
+    fn locate(x: usize, y: usize, z: usize) -> (usize, usize) {
+        assert!(
+            x < CHUNK_SIZE && y < CHUNK_HEIGHT && z < CHUNK_SIZE,
+            "block ({}, {}, {}) outside of chunk",
+            x,
+            y,
+            z
+        );
+        let section = y / CHUNK_SIZE;
+        let index = ((y % CHUNK_SIZE) * CHUNK_SIZE + z) * CHUNK_SIZE + x;
+        (section, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: BlockId = BlockId(1);
+    const DIRT: BlockId = BlockId(2);
+
+    #[test]
+    fn get_and_set_across_sections() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        assert_eq!(chunk.set(3, 17, 5, STONE), BlockId::AIR);
+        assert_eq!(chunk.set(3, 17, 5, DIRT), STONE);
+        chunk.set(15, 255, 15, STONE);
+        assert_eq!((chunk.get(3, 17, 5), chunk.get(15, 255, 15), chunk.get(3, 16, 5)), (DIRT, STONE, BlockId::AIR));
+        assert!(chunk.is_section_empty(0) && !chunk.is_section_empty(1) && !chunk.is_section_empty(15));
+    }
+
+    #[test]
+    fn fill_box_resets_whole_sections() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(4, 20, 4, DIRT);
+        let max = CHUNK_SIZE as i32 - 1;
+        chunk.fill_box(IVec3::new(0, 10, 0), IVec3::new(max, 40, max), STONE);
+
+        // Sections 1 (16..32) is wholly inside and drops its index storage,
+        // even though it held dirt before; 0 and 2 are only partly covered.
+        assert_eq!(chunk.sections[1].bits_per_entry(), 0);
+        assert_eq!(chunk.sections[1].uniform_value(), Some(STONE));
+        assert_eq!(chunk.sections[0].bits_per_entry(), 1);
+        assert_eq!((chunk.get(0, 9, 0), chunk.get(0, 10, 0)), (BlockId::AIR, STONE));
+        assert_eq!((chunk.get(9, 40, 9), chunk.get(9, 41, 9)), (STONE, BlockId::AIR));
+        assert_eq!(chunk.non_air_count(), 31 * CHUNK_SIZE * CHUNK_SIZE);
+    }
+
+    #[test]
+    fn fill_box_accepts_corners_in_any_order() {
+        let mut a = Chunk::new(ChunkPos::new(0, 0));
+        let mut b = Chunk::new(ChunkPos::new(0, 0));
+        a.fill_box(IVec3::new(2, 3, 4), IVec3::new(6, 5, 7), STONE);
+        b.fill_box(IVec3::new(6, 5, 7), IVec3::new(2, 3, 4), STONE);
+        assert_eq!(a, b);
+        assert_eq!(a.non_air_count(), 5 * 3 * 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "leaves the chunk")]
+    fn fill_box_outside_panics() {
+        Chunk::new(ChunkPos::new(0, 0)).fill_box(IVec3::new(0, 0, 0), IVec3::new(16, 0, 0), STONE);
+    }
+
+    #[test]
+    fn iter_non_air_lists_blocks_bottom_to_top() {
+        let mut chunk = Chunk::new(ChunkPos::new(2, -1));
+        chunk.set(5, 200, 1, DIRT);
+        chunk.set(1, 2, 3, STONE);
+        chunk.set(0, 2, 3, DIRT);
+        let blocks: Vec<_> = chunk.iter_non_air().collect();
+        assert_eq!(
+            blocks,
+            [(IVec3::new(0, 2, 3), DIRT), (IVec3::new(1, 2, 3), STONE), (IVec3::new(5, 200, 1), DIRT)]
+        );
+        assert_eq!(chunk.non_air_count(), 3);
+        assert_eq!(Chunk::new(ChunkPos::new(0, 0)).iter_non_air().count(), 0);
+    }
+
+    #[test]
+    fn memory_usage_follows_contents() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        let empty = chunk.memory_usage();
+        for i in 0..CHUNK_SIZE {
+            chunk.set(i, i * 3, i, BlockId(i as u16));
+        }
+        let varied = chunk.memory_usage();
+        assert!(varied > empty);
+
+        chunk.fill(STONE);
+        assert_eq!(chunk.memory_usage(), empty);
+        chunk.set_light(0, 0, 0, LightChannel::Block, 7);
+        assert!(chunk.memory_usage() > empty);
+    }
+
+    #[test]
+    fn content_hash_ignores_storage_layout() {
+        let mut a = Chunk::new(ChunkPos::new(0, 0));
+        let mut b = Chunk::new(ChunkPos::new(0, 0));
+        a.set(1, 1, 1, DIRT);
+        a.set(1, 1, 1, BlockId::AIR);
+        assert_ne!(a, b, "a keeps a palette slot for dirt");
+        assert_eq!(a.content_hash(), b.content_hash());
+        b.set(1, 1, 1, STONE);
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+}
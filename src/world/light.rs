@@ -0,0 +1,83 @@
+use std::mem::size_of;
+
+/// Brightest light level of either channel.
+pub const MAX_LIGHT: u8 = 15;
+
+/// The two kinds of light stored per block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LightChannel {
+    /// Daylight falling from above the world.
+    Sky,
+    /// Light emitted by blocks such as torches.
+    Block,
+}
+
+impl LightChannel {
+    pub const ALL: [LightChannel; 2] = [LightChannel::Sky, LightChannel::Block];
+
+    fn shift(self) -> u32 {
+        match self {
+            LightChannel::Sky => 4,
+            LightChannel::Block => 0,
+        }
+    }
+}
+
+/// Sky and block light of one section, 4 bits each, packed into a byte per
+/// block with sky light in the high nibble.
+///
+/// A section whose blocks all have the same light, such as open sky or
+/// solid rock, keeps a single byte and allocates on the first differing
+/// write.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LightSection {
+    len: usize,
+    uniform: u8,
+    data: Option<Box<[u8]>>,
+}
+
+impl LightSection {
+    /// A section of `len` blocks, all dark.
+    pub fn new(len: usize) -> Self {
+        LightSection { len, uniform: 0, data: None }
+    }
+
+    pub fn get(&self, channel: LightChannel, index: usize) -> u8 {
+        assert!(index < self.len, "light index {} out of range", index);
+        let packed = self.data.as_ref().map_or(self.uniform, |data| data[index]);
+        (packed >> channel.shift()) & 0x0f
+    }
+
+    pub fn set(&mut self, channel: LightChannel, index: usize, level: u8) {
+        assert!(index < self.len, "light index {} out of range", index);
+        assert!(level <= MAX_LIGHT, "light level {} above {}", level, MAX_LIGHT);
+        let shift = channel.shift();
+        let packed = |byte: u8| (byte & !(0x0f << shift)) | (level << shift);
+        match &mut self.data {
+            Some(data) => data[index] = packed(data[index]),
+            None if packed(self.uniform) == self.uniform => {}
+            None => {
+                let mut data = vec![self.uniform; self.len].into_boxed_slice();
+                data[index] = packed(self.uniform);
+                self.data = Some(data);
+            }
+        }
+    }
+
+    /// Sets both channels of every block, dropping the per-block storage.
+    pub fn fill(&mut self, sky: u8, block: u8) {
+        assert!(sky <= MAX_LIGHT && block <= MAX_LIGHT, "light level above {}", MAX_LIGHT);
+        self.uniform = (sky << 4) | block;
+        self.data = None;
+    }
+
+    /// Both channels of every block, packed as stored: sky light in the
+    /// high nibble.
+    pub fn iter_packed(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len).map(|index| self.data.as_ref().map_or(self.uniform, |data| data[index]))
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>() + self.data.as_ref().map_or(0, |data| data.len())
+    }
+}
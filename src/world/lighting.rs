@@ -0,0 +1,440 @@
+//! Flood-fill light propagation.
+//!
+//! Both channels spread breadth-first from their sources, losing one level
+//! per block and more through blocks with a higher `light_opacity`; opaque
+//! blocks stop light. Sky light enters at the top of the world and falls
+//! straight down at full strength until something absorbs it. Block light
+//! starts at each block's `light_emission`.
+//!
+//! Changes are handled incrementally: light that came from the changed
+//! blocks is flooded back to zero first, then the surrounding light that
+//! survived, plus any new sources, is spread into the gap.
+
+use std::collections::VecDeque;
+
+use crate::block::BlockRegistry;
+use crate::math::IVec3;
+
+use super::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
+use super::light::{LightChannel, MAX_LIGHT};
+
+const DIRECTIONS: [IVec3; 6] = [
+    IVec3::new(1, 0, 0),
+    IVec3::new(-1, 0, 0),
+    IVec3::new(0, 1, 0),
+    IVec3::new(0, -1, 0),
+    IVec3::new(0, 0, 1),
+    IVec3::new(0, 0, -1),
+];
+
+/// Blocks and light the engine works on, addressed by position.
+pub(super) trait LightStore {
+    /// Light lost entering the block at `pos`, or `None` where the store
+    /// holds no block (outside the world, unloaded chunks); light never
+    /// spreads there.
+    fn opacity(&self, pos: IVec3) -> Option<u8>;
+
+    fn emission(&self, pos: IVec3) -> u8;
+
+    /// Light at `pos`. Above the world sky light is full, everywhere else
+    /// outside the store it is dark.
+    fn light(&self, pos: IVec3, channel: LightChannel) -> u8;
+
+    /// Ignored outside the store.
+    fn set_light(&mut self, pos: IVec3, channel: LightChannel, level: u8);
+}
+
+/// Light reaching a neighbour of a block lit at `level`.
+fn spread(channel: LightChannel, level: u8, down: bool, opacity: u8) -> u8 {
+    if channel == LightChannel::Sky && level == MAX_LIGHT && down {
+        MAX_LIGHT.saturating_sub(opacity)
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Spreads light outwards from every queued block.
+pub(super) fn propagate(store: &mut impl LightStore, channel: LightChannel, mut queue: VecDeque<IVec3>) {
+    while let Some(pos) = queue.pop_front() {
+        let level = store.light(pos, channel);
+        if level <= 1 {
+            continue;
+        }
+        for dir in DIRECTIONS {
+            let next = pos + dir;
+            let Some(opacity) = store.opacity(next) else { continue };
+            let lit = spread(channel, level, dir.y < 0, opacity);
+            if lit > store.light(next, channel) {
+                store.set_light(next, channel, lit);
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+/// Floods the light that came from the queued blocks (with the level each
+/// had) back to zero. Blocks lit from elsewhere that border the darkened
+/// area, and emitters inside it, are queued in `relight`.
+fn unlight(
+    store: &mut impl LightStore,
+    channel: LightChannel,
+    mut queue: VecDeque<(IVec3, u8)>,
+    relight: &mut VecDeque<IVec3>,
+) {
+    while let Some((pos, level)) = queue.pop_front() {
+        for dir in DIRECTIONS {
+            let next = pos + dir;
+            if store.opacity(next).is_none() {
+                continue;
+            }
+            let current = store.light(next, channel);
+            if current == 0 {
+                continue;
+            }
+            let falling = channel == LightChannel::Sky && dir.y < 0 && level == MAX_LIGHT && current == MAX_LIGHT;
+            if current < level || falling {
+                store.set_light(next, channel, 0);
+                queue.push_back((next, current));
+                let emission = store.emission(next);
+                if channel == LightChannel::Block && emission > 0 {
+                    store.set_light(next, channel, emission);
+                    relight.push_back(next);
+                }
+            } else {
+                relight.push_back(next);
+            }
+        }
+    }
+}
+
+/// Brings the light around `changed` up to date after the blocks there
+/// were replaced.
+pub(super) fn relight(store: &mut impl LightStore, changed: &[IVec3]) {
+    for channel in LightChannel::ALL {
+        let mut removed = VecDeque::new();
+        let mut relight = VecDeque::new();
+        for &pos in changed {
+            let level = store.light(pos, channel);
+            if level > 0 {
+                store.set_light(pos, channel, 0);
+                removed.push_back((pos, level));
+            }
+        }
+        unlight(store, channel, removed, &mut relight);
+
+        for &pos in changed {
+            let emission = store.emission(pos);
+            if channel == LightChannel::Block && emission > store.light(pos, channel) {
+                store.set_light(pos, channel, emission);
+                relight.push_back(pos);
+            }
+            // Let the neighbours, and the sky above the top block, shine in.
+            relight.extend(DIRECTIONS.iter().map(|&dir| pos + dir));
+        }
+        propagate(store, channel, relight);
+    }
+}
+
+/// A single chunk, with nothing around it.
+struct ChunkStore<'a> {
+    chunk: &'a mut Chunk,
+    registry: &'a BlockRegistry,
+}
+
+impl ChunkStore<'_> {
+    fn local(pos: IVec3) -> Option<(usize, usize, usize)> {
+        Chunk::contains(pos.x, pos.y, pos.z).then_some((pos.x as usize, pos.y as usize, pos.z as usize))
+    }
+}
+
+impl LightStore for ChunkStore<'_> {
+    fn opacity(&self, pos: IVec3) -> Option<u8> {
+        let (x, y, z) = Self::local(pos)?;
+        Some(self.registry.def(self.chunk.get(x, y, z)).light_opacity)
+    }
+
+    fn emission(&self, pos: IVec3) -> u8 {
+        Self::local(pos).map_or(0, |(x, y, z)| self.registry.def(self.chunk.get(x, y, z)).light_emission)
+    }
+
+    fn light(&self, pos: IVec3, channel: LightChannel) -> u8 {
+        match Self::local(pos) {
+            Some((x, y, z)) => self.chunk.light(x, y, z, channel),
+            None if pos.y >= CHUNK_HEIGHT as i32 && channel == LightChannel::Sky => MAX_LIGHT,
+            None => 0,
+        }
+    }
+
+    fn set_light(&mut self, pos: IVec3, channel: LightChannel, level: u8) {
+        if let Some((x, y, z)) = Self::local(pos) {
+            self.chunk.set_light(x, y, z, channel, level);
+        }
+    }
+}
+
+/// Computes the light of `chunk` from scratch, as if it stood alone: no
+/// light enters through its sides. `World` runs this on every generated
+/// chunk and then lets light flow across the borders.
+pub fn light_chunk(chunk: &mut Chunk, registry: &BlockRegistry) {
+    let opacity = |chunk: &Chunk, x, y, z| registry.def(chunk.get(x, y, z)).light_opacity;
+
+    // Lowest block of each column still in full daylight.
+    let mut heights = [CHUNK_HEIGHT; CHUNK_SIZE * CHUNK_SIZE];
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let height = &mut heights[z * CHUNK_SIZE + x];
+            while *height > 0 && opacity(chunk, x, *height - 1, z) == 0 {
+                *height -= 1;
+            }
+        }
+    }
+    let lowest = heights.iter().copied().min().unwrap_or(CHUNK_HEIGHT);
+    let highest = heights.iter().copied().max().unwrap_or(CHUNK_HEIGHT);
+    // Sections above every column top are uniformly lit; keep them compact.
+    let open = highest.div_ceil(CHUNK_SIZE);
+    chunk.fill_light(0, 0);
+    for section in open..CHUNK_HEIGHT / CHUNK_SIZE {
+        chunk.fill_section_light(section, MAX_LIGHT, 0);
+    }
+    for y in lowest..(open * CHUNK_SIZE).min(CHUNK_HEIGHT) {
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                if y >= heights[z * CHUNK_SIZE + x] {
+                    chunk.set_light(x, y, z, LightChannel::Sky, MAX_LIGHT);
+                }
+            }
+        }
+    }
+
+    // Daylight only spreads sideways below the tops of neighbouring columns,
+    // and down into whatever partly absorbed it.
+    let mut sky = VecDeque::new();
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let height = heights[z * CHUNK_SIZE + x];
+            let mut reach = height + 1;
+            for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let (nx, nz) = (x as i32 + dx, z as i32 + dz);
+                if Chunk::contains(nx, 0, nz) {
+                    reach = reach.max(heights[nz as usize * CHUNK_SIZE + nx as usize]);
+                }
+            }
+            sky.extend((height..reach.min(CHUNK_HEIGHT)).map(|y| IVec3::new(x as i32, y as i32, z as i32)));
+        }
+    }
+
+    let mut emitters = VecDeque::new();
+    let sources: Vec<_> = chunk
+        .iter_non_air()
+        .map(|(pos, block)| (pos, registry.def(block).light_emission))
+        .filter(|&(_, emission)| emission > 0)
+        .collect();
+    for (pos, emission) in sources {
+        chunk.set_light(pos.x as usize, pos.y as usize, pos.z as usize, LightChannel::Block, emission);
+        emitters.push_back(pos);
+    }
+
+    let mut store = ChunkStore { chunk, registry };
+    propagate(&mut store, LightChannel::Sky, sky);
+    propagate(&mut store, LightChannel::Block, emitters);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockId;
+    use crate::world::{ChunkPos, World};
+    use crate::worldgen::{FeatureBlock, Replace, TerrainGenerator};
+
+    use LightChannel::{Block, Sky};
+
+    /// Stone up to y = 3, optionally a stone roof at y = 10 with a hole, and
+    /// optionally a glowstone feature emitted by chunk (0, 0).
+    struct Shelter {
+        stone: BlockId,
+        glowstone: BlockId,
+        roof: bool,
+        hole: Option<IVec3>,
+        feature: Option<IVec3>,
+    }
+
+    impl Shelter {
+        fn new(roof: bool) -> Self {
+            let registry = BlockRegistry::builtin();
+            Shelter {
+                stone: registry.expect_id("stone"),
+                glowstone: registry.expect_id("glowstone"),
+                roof,
+                hole: None,
+                feature: None,
+            }
+        }
+    }
+
+    impl TerrainGenerator for Shelter {
+        fn seed(&self) -> u64 {
+            0
+        }
+
+        fn generate(&self, pos: ChunkPos) -> Chunk {
+            let mut chunk = Chunk::new(pos);
+            chunk.fill_box(IVec3::new(0, 0, 0), IVec3::new(15, 3, 15), self.stone);
+            if self.roof {
+                chunk.fill_box(IVec3::new(0, 10, 0), IVec3::new(15, 10, 15), self.stone);
+            }
+            if let Some(hole) = self.hole.map(|hole| hole - pos.origin()) {
+                if Chunk::contains(hole.x, hole.y, hole.z) {
+                    chunk.set(hole.x as usize, hole.y as usize, hole.z as usize, BlockId::AIR);
+                }
+            }
+            chunk
+        }
+
+        fn decorate(&self, chunk: &Chunk) -> Vec<FeatureBlock> {
+            match self.feature {
+                Some(pos) if chunk.pos() == ChunkPos::new(0, 0) => {
+                    vec![FeatureBlock { pos, block: self.glowstone, replace: Replace::Air, rank: 0 }]
+                }
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    /// The chunks within one chunk of the origin.
+    fn world(generator: Shelter) -> World {
+        let mut world = World::new(BlockRegistry::builtin(), generator);
+        world.generate_around(ChunkPos::new(0, 0), 1);
+        world
+    }
+
+    fn at(x: i32, y: i32, z: i32) -> IVec3 {
+        IVec3::new(x, y, z)
+    }
+
+    /// Light of `channel` in the box `min..=max`.
+    fn levels(world: &World, channel: LightChannel, min: IVec3, max: IVec3) -> Vec<u8> {
+        let mut levels = Vec::new();
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    levels.push(world.light(at(x, y, z), channel));
+                }
+            }
+        }
+        levels
+    }
+
+    #[test]
+    fn torch_spreads_and_unlights() {
+        let mut world = world(Shelter::new(false));
+        let torch = world.registry().expect_id("torch");
+        world.set_block(at(8, 4, 8), torch);
+
+        for (pos, level) in [
+            (at(8, 4, 8), 14),
+            (at(9, 4, 8), 13),
+            (at(8, 5, 8), 13),
+            (at(8, 4, 11), 11),
+            (at(5, 6, 7), 8),
+            (at(8, 17, 8), 1),
+            (at(8, 18, 8), 0),
+            (at(8, 3, 8), 0),
+        ] {
+            assert_eq!(world.light(pos, Block), level, "{:?}", pos);
+        }
+        // Sky light is unaffected.
+        assert_eq!(world.light(at(9, 4, 8), Sky), MAX_LIGHT);
+
+        world.set_block(at(8, 4, 8), BlockId::AIR);
+        assert!(levels(&world, Block, at(-8, 0, -8), at(24, 20, 24)).iter().all(|&level| level == 0));
+    }
+
+    #[test]
+    fn opaque_block_shades_the_sky_column() {
+        let mut world = world(Shelter::new(false));
+        let stone = world.registry().expect_id("stone");
+        world.set_block(at(8, 10, 8), stone);
+
+        assert_eq!(world.light(at(8, 11, 8), Sky), 15);
+        assert_eq!(world.light(at(8, 10, 8), Sky), 0);
+        // Under the block only light from the open columns beside it arrives.
+        for y in 4..10 {
+            assert_eq!(world.light(at(8, y, 8), Sky), 14, "y = {}", y);
+        }
+        assert_eq!(world.light(at(9, 9, 8), Sky), 15);
+
+        world.set_block(at(8, 10, 8), BlockId::AIR);
+        assert!(levels(&world, Sky, at(0, 4, 0), at(15, 20, 15)).iter().all(|&level| level == MAX_LIGHT));
+    }
+
+    #[test]
+    fn hole_in_the_roof() {
+        let mut world = world(Shelter::new(true));
+        assert!(levels(&world, Sky, at(-16, 4, -16), at(31, 9, 31)).iter().all(|&level| level == 0));
+
+        world.set_block(at(8, 10, 8), BlockId::AIR);
+        for y in 4..=10 {
+            assert_eq!(world.light(at(8, y, 8), Sky), 15, "y = {}", y);
+        }
+        let around = [(at(9, 4, 8), 14), (at(8, 9, 7), 14), (at(8, 4, 11), 12), (at(10, 5, 9), 12), (at(8, 4, 22), 1)];
+        for (pos, level) in around {
+            assert_eq!(world.light(pos, Sky), level, "{:?}", pos);
+        }
+        assert_eq!(world.light(at(8, 4, 23), Sky), 0);
+
+        // The same roof generated with the hole lights the same.
+        let generated = world_with_hole(at(8, 10, 8));
+        for channel in LightChannel::ALL {
+            assert_eq!(
+                levels(&world, channel, at(-16, 0, -16), at(31, 12, 31)),
+                levels(&generated, channel, at(-16, 0, -16), at(31, 12, 31))
+            );
+        }
+
+        let stone = world.registry().expect_id("stone");
+        world.set_block(at(8, 10, 8), stone);
+        assert!(levels(&world, Sky, at(-16, 4, -16), at(31, 9, 31)).iter().all(|&level| level == 0));
+    }
+
+    fn world_with_hole(hole: IVec3) -> World {
+        world(Shelter { hole: Some(hole), ..Shelter::new(true) })
+    }
+
+    #[test]
+    fn light_crosses_chunk_borders() {
+        let mut world = world(Shelter::new(true));
+        let torch = world.registry().expect_id("torch");
+        world.set_block(at(14, 4, 8), torch);
+        assert_eq!(world.light(at(15, 4, 8), Block), 13);
+        assert_eq!(world.light(at(16, 4, 8), Block), 12);
+        assert_eq!(world.light(at(17, 5, 9), Block), 9);
+        assert_eq!(world.light(at(14, 4, -1), Block), 5);
+        assert_eq!(world.light(at(-1, 4, 8), Block), 0);
+
+        // Sky light through a hole right on the border.
+        let world = world_with_hole(at(15, 10, 8));
+        assert_eq!(world.light(at(15, 4, 8), Sky), 15);
+        assert_eq!(world.light(at(16, 4, 8), Sky), 14);
+        assert_eq!(world.light(at(18, 4, 9), Sky), 11);
+    }
+
+    #[test]
+    fn feature_light_lands_in_a_neighbouring_chunk() {
+        let expected =
+            [(at(17, 5, 8), 15), (at(16, 5, 8), 14), (at(15, 5, 8), 13), (at(12, 4, 8), 9), (at(17, 5, 21), 2)];
+        let generator = || Shelter { feature: Some(at(17, 5, 8)), ..Shelter::new(true) };
+
+        // The feature's chunk generated after the one it lands in, and before.
+        for order in [[ChunkPos::new(1, 0), ChunkPos::new(0, 0)], [ChunkPos::new(0, 0), ChunkPos::new(1, 0)]] {
+            let mut world = World::new(BlockRegistry::builtin(), generator());
+            for pos in order {
+                world.generate_chunk(pos);
+            }
+            world.generate_around(ChunkPos::new(0, 0), 1);
+            assert_eq!(world.block(at(17, 5, 8)), Some(world.registry().expect_id("glowstone")));
+            for (pos, level) in expected {
+                assert_eq!(world.light(pos, Block), level, "{:?} after {:?}", pos, order);
+            }
+        }
+    }
+}
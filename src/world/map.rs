@@ -0,0 +1,431 @@
+//! The loaded world: generated chunks plus the bookkeeping that lets
+//! features and light cross chunk borders.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::block::{BlockId, BlockRegistry};
+use crate::math::IVec3;
+use crate::mesher::ChunkNeighbors;
+use crate::worldgen::{FeatureBlock, TerrainGenerator};
+
+use super::chunk::{Chunk, ChunkPos, CHUNK_HEIGHT, CHUNK_SIZE};
+use super::light::{LightChannel, MAX_LIGHT};
+use super::lighting::{self, LightStore};
+
+/// Which feature last wrote a block, and what the terrain had there before.
+#[derive(Clone, Copy, Debug)]
+struct Claim {
+    rank: u64,
+    base: BlockId,
+}
+
+/// Claim rank of blocks set with `World::set_block`, which outranks every feature.
+const PLAYER_RANK: u64 = u64::MAX;
+
+/// Chunks generated on demand by a `TerrainGenerator`.
+///
+/// Generating a chunk runs the generator's terrain stage, then its decoration
+/// stage. Feature blocks aimed at chunks that exist are written straight
+/// away; the rest wait in `pending` until their chunk is generated. Each
+/// written feature block leaves a claim so that where features overlap the
+/// highest-ranked one wins and replacement rules are checked against the
+/// undecorated terrain, which makes the result independent of the order
+/// chunks are generated in. Blocks set with `set_block` are claimed too, so
+/// features landing later never overwrite them. Features reach no further
+/// than the neighbouring chunks, so a chunk's claims are dropped once all
+/// eight of its neighbours are generated.
+///
+/// Once its blocks are in place a new chunk is lit on its own and light is
+/// then spread across its borders; later block changes, including feature
+/// blocks landing in chunks generated earlier, are relit incrementally.
+pub struct World {
+    registry: BlockRegistry,
+    generator: Box<dyn TerrainGenerator>,
+    chunks: HashMap<ChunkPos, Chunk>,
+    pending: HashMap<ChunkPos, Vec<FeatureBlock>>,
+    claims: HashMap<ChunkPos, HashMap<usize, Claim>>,
+}
+
+impl World {
+    pub fn new(registry: BlockRegistry, generator: impl TerrainGenerator + 'static) -> Self {
+        World {
+            registry,
+            generator: Box::new(generator),
+            chunks: HashMap::new(),
+            pending: HashMap::new(),
+            claims: HashMap::new(),
+        }
+    }
+
+    pub fn registry(&self) -> &BlockRegistry {
+        &self.registry
+    }
+
+    pub fn generator(&self) -> &dyn TerrainGenerator {
+        self.generator.as_ref()
+    }
+
+    pub fn chunk(&self, pos: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&pos)
+    }
+
+    pub fn chunk_mut(&mut self, pos: ChunkPos) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&pos)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    pub fn is_generated(&self, pos: ChunkPos) -> bool {
+        self.chunks.contains_key(&pos)
+    }
+
+    /// Generates the chunk at `pos` unless it already exists.
+    pub fn generate_chunk(&mut self, pos: ChunkPos) -> &Chunk {
+        if !self.chunks.contains_key(&pos) {
+            let chunk = self.generator.generate(pos);
+            // Decorate from the bare terrain, before other chunks' features land on it.
+            let features = self.generator.decorate(&chunk);
+            self.chunks.insert(pos, chunk);
+
+            for block in self.pending.remove(&pos).unwrap_or_default() {
+                self.place(block);
+            }
+            // Blocks landing in already lit chunks are relit once this one is.
+            let mut changed = Vec::new();
+            for block in features {
+                let target = ChunkPos::containing(block.pos);
+                if target == pos {
+                    self.place(block);
+                } else if self.chunks.contains_key(&target) {
+                    if self.place(block) {
+                        changed.push(block.pos);
+                    }
+                } else {
+                    self.pending.entry(target).or_default().push(block);
+                }
+            }
+
+            // Chunks now surrounded by generated ones will receive no more feature blocks.
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    if self.is_surrounded(pos.offset(dx, dz)) {
+                        self.claims.remove(&pos.offset(dx, dz));
+                    }
+                }
+            }
+
+            lighting::light_chunk(self.chunks.get_mut(&pos).expect("chunk was just inserted"), &self.registry);
+            self.spread_light_into(pos);
+            lighting::relight(self, &changed);
+        }
+        &self.chunks[&pos]
+    }
+
+    /// Generates every chunk within `radius` chunks of `center` (a square).
+    pub fn generate_around(&mut self, center: ChunkPos, radius: i32) {
+        for dz in -radius..=radius {
+            for dx in -radius..=radius {
+                self.generate_chunk(center.offset(dx, dz));
+            }
+        }
+    }
+
+    /// Feature blocks waiting for their chunk to be generated.
+    pub fn pending_count(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// The block at world `pos`, or `None` if its chunk is not generated or
+    /// `pos` is above or below the world.
+    pub fn block(&self, pos: IVec3) -> Option<BlockId> {
+        let (chunk, local) = self.locate(pos)?;
+        Some(self.chunks.get(&chunk)?.get(local.x as usize, local.y as usize, local.z as usize))
+    }
+
+    /// Sets the block at world `pos` and relights around it, returning the
+    /// previous block, or `None` (and changing nothing) if its chunk is not
+    /// generated. Features of chunks generated later leave the block alone.
+    pub fn set_block(&mut self, pos: IVec3, block: BlockId) -> Option<BlockId> {
+        let (chunk_pos, local) = self.locate(pos)?;
+        let surrounded = self.is_surrounded(chunk_pos);
+        let chunk = self.chunks.get_mut(&chunk_pos)?;
+        let (x, y, z) = (local.x as usize, local.y as usize, local.z as usize);
+        let old = chunk.set(x, y, z, block);
+        if !surrounded {
+            let index = (y * CHUNK_SIZE + z) * CHUNK_SIZE + x;
+            let claims = self.claims.entry(chunk_pos).or_default();
+            let base = claims.get(&index).map_or(old, |claim| claim.base);
+            claims.insert(index, Claim { rank: PLAYER_RANK, base });
+        }
+        if old != block {
+            lighting::relight(self, &[pos]);
+        }
+        Some(old)
+    }
+
+    /// Light level of `channel` at world `pos`. Above the world sky light is
+    /// full; below it and in chunks that are not generated it is dark.
+    pub fn light(&self, pos: IVec3, channel: LightChannel) -> u8 {
+        match self.locate(pos) {
+            Some((chunk, local)) => self.chunks.get(&chunk).map_or(0, |chunk| {
+                chunk.light(local.x as usize, local.y as usize, local.z as usize, channel)
+            }),
+            None if pos.y >= CHUNK_HEIGHT as i32 && channel == LightChannel::Sky => MAX_LIGHT,
+            None => 0,
+        }
+    }
+
+    /// The generated chunks around `pos`, for meshing.
+    pub fn neighbors(&self, pos: ChunkPos) -> ChunkNeighbors<'_> {
+        ChunkNeighbors::from_fn(pos, |pos| self.chunks.get(&pos))
+    }
+
+    /// Whether the chunk at `pos` and all eight chunks around it are generated.
+    fn is_surrounded(&self, pos: ChunkPos) -> bool {
+        (-1..=1).all(|dz| (-1..=1).all(|dx| self.chunks.contains_key(&pos.offset(dx, dz))))
+    }
+
+    /// Chunk and local coordinates of world `pos`.
+    fn locate(&self, pos: IVec3) -> Option<(ChunkPos, IVec3)> {
+        let chunk = ChunkPos::containing(pos);
+        let local = pos - chunk.origin();
+        Chunk::contains(local.x, local.y, local.z).then_some((chunk, local))
+    }
+
+    /// Lets light flow between the freshly lit chunk at `pos` and the
+    /// generated chunks beside it, in both directions.
+    fn spread_light_into(&mut self, pos: ChunkPos) {
+        let size = CHUNK_SIZE as i32;
+        let origin = pos.origin();
+        for channel in LightChannel::ALL {
+            let mut queue = VecDeque::new();
+            for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                if !self.chunks.contains_key(&pos.offset(dx, dz)) {
+                    continue;
+                }
+                for y in 0..CHUNK_HEIGHT as i32 {
+                    for i in 0..size {
+                        // A border block of this chunk and the one facing it.
+                        let (x, z) = match (dx, dz) {
+                            (0, _) => (i, if dz > 0 { size - 1 } else { 0 }),
+                            _ => (if dx > 0 { size - 1 } else { 0 }, i),
+                        };
+                        let inside = origin + IVec3::new(x, y, z);
+                        let outside = inside + IVec3::new(dx, 0, dz);
+                        let (a, b) = (self.light(inside, channel), self.light(outside, channel));
+                        if a > b + 1 {
+                            queue.push_back(inside);
+                        } else if b > a + 1 {
+                            queue.push_back(outside);
+                        }
+                    }
+                }
+            }
+            lighting::propagate(self, channel, queue);
+        }
+    }
+
+    /// Writes a feature block into its (generated) chunk if it outranks the
+    /// feature already there and may replace the terrain underneath.
+    /// Returns whether it was written.
+    fn place(&mut self, block: FeatureBlock) -> bool {
+        let Some((pos, local)) = self.locate(block.pos) else { return false };
+        let Some(chunk) = self.chunks.get_mut(&pos) else { return false };
+        let (x, y, z) = (local.x as usize, local.y as usize, local.z as usize);
+        let index = (y * CHUNK_SIZE + z) * CHUNK_SIZE + x;
+
+        let claims = self.claims.entry(pos).or_default();
+        let claim = claims.get(&index).copied();
+        // Equal ranks come from the same feature, whose later blocks win.
+        if claim.is_some_and(|claim| claim.rank > block.rank) {
+            return false;
+        }
+        let base = claim.map_or_else(|| chunk.get(x, y, z), |claim| claim.base);
+        if !self.generator.can_replace(block.replace, base) {
+            return false;
+        }
+        chunk.set(x, y, z, block.block);
+        claims.insert(index, Claim { rank: block.rank, base });
+        true
+    }
+}
+
+impl LightStore for World {
+    fn opacity(&self, pos: IVec3) -> Option<u8> {
+        Some(self.registry.def(self.block(pos)?).light_opacity)
+    }
+
+    fn emission(&self, pos: IVec3) -> u8 {
+        self.block(pos).map_or(0, |block| self.registry.def(block).light_emission)
+    }
+
+    fn light(&self, pos: IVec3, channel: LightChannel) -> u8 {
+        World::light(self, pos, channel)
+    }
+
+    fn set_light(&mut self, pos: IVec3, channel: LightChannel, level: u8) {
+        let Some((chunk, local)) = self.locate(pos) else { return };
+        if let Some(chunk) = self.chunks.get_mut(&chunk) {
+            chunk.set_light(local.x as usize, local.y as usize, local.z as usize, channel, level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worldgen::tests::generation_orders;
+    use crate::worldgen::{hash_coords, OverworldGenerator, Replace};
+
+    /// Stone up to y = 3. Every chunk at a multiple of `every` on both axes
+    /// gets a bar reaching six blocks into the chunks east and west of it,
+    /// at a height and of a block picked by its rank, so bars of neighbouring
+    /// chunks overlap; and a post that replaces the stone of its west
+    /// neighbour.
+    struct BarGenerator {
+        registry: BlockRegistry,
+        every: i32,
+    }
+
+    impl TerrainGenerator for BarGenerator {
+        fn seed(&self) -> u64 {
+            1
+        }
+
+        fn generate(&self, pos: ChunkPos) -> Chunk {
+            let mut chunk = Chunk::new(pos);
+            chunk.fill_box(IVec3::new(0, 0, 0), IVec3::new(15, 3, 15), self.registry.expect_id("stone"));
+            chunk
+        }
+
+        fn decorate(&self, chunk: &Chunk) -> Vec<FeatureBlock> {
+            let pos = chunk.pos();
+            if pos.x % self.every != 0 || pos.z % self.every != 0 {
+                return Vec::new();
+            }
+            let rank = hash_coords(self.seed(), pos.x, 0, pos.z);
+            let bar = self.registry.expect_id(if rank & 2 == 0 { "planks" } else { "glass" });
+            let post = self.registry.expect_id("cobblestone");
+            let origin = pos.origin();
+            let bars = (-6..22).map(|x| FeatureBlock {
+                pos: origin + IVec3::new(x, 4 + (rank & 1) as i32, 7),
+                block: bar,
+                replace: Replace::Air,
+                rank,
+            });
+            let posts = (0..8).map(|y| FeatureBlock {
+                pos: origin + IVec3::new(-1, y, 3),
+                block: post,
+                replace: Replace::Any,
+                rank,
+            });
+            bars.chain(posts).collect()
+        }
+    }
+
+    fn bar_world(every: i32) -> World {
+        let registry = BlockRegistry::builtin();
+        World::new(registry.clone(), BarGenerator { registry, every })
+    }
+
+    /// Non-air blocks of every chunk of `world` generated in `order`.
+    fn blocks_in_order(mut world: World, order: &[ChunkPos]) -> HashMap<ChunkPos, Vec<(IVec3, BlockId)>> {
+        for &pos in order {
+            world.generate_chunk(pos);
+        }
+        // Nothing may still be waiting for a chunk that exists.
+        assert!(world.pending.keys().all(|pos| !world.is_generated(*pos)));
+        world.chunks.iter().map(|(&pos, chunk)| (pos, chunk.iter_non_air().collect())).collect()
+    }
+
+    #[test]
+    fn overlapping_features_do_not_depend_on_generation_order() {
+        let [row_major, others @ ..] = generation_orders(2);
+        let expected = blocks_in_order(bar_world(1), &row_major);
+        for order in others {
+            assert_eq!(blocks_in_order(bar_world(1), &order), expected);
+        }
+
+        // Where bars overlap the higher rank wins.
+        let world = {
+            let mut world = bar_world(1);
+            world.generate_around(ChunkPos::new(0, 0), 1);
+            world
+        };
+        let rank = |x, z| hash_coords(1, x, 0, z);
+        let winner = if rank(0, 0) > rank(1, 0) { 0 } else { 1 };
+        let bar = if rank(winner, 0) & 2 == 0 { "planks" } else { "glass" };
+        let y = 4 + (rank(winner, 0) & 1) as i32;
+        assert_eq!(world.block(IVec3::new(17, y, 7)), Some(world.registry().expect_id(bar)));
+    }
+
+    #[test]
+    fn overworld_does_not_depend_on_generation_order() {
+        let registry = BlockRegistry::builtin();
+        let world = || World::new(registry.clone(), OverworldGenerator::new(4, &registry));
+        let [row_major, others @ ..] = generation_orders(2);
+        let expected = blocks_in_order(world(), &row_major);
+        for order in others {
+            assert_eq!(blocks_in_order(world(), &order), expected);
+        }
+    }
+
+    #[test]
+    fn pending_blocks_drain_once_neighbours_exist() {
+        let mut world = bar_world(100);
+        let cobblestone = world.registry().expect_id("cobblestone");
+        world.generate_chunk(ChunkPos::new(0, 0));
+        // Six bar blocks each way and the eight post blocks to the west.
+        assert_eq!(world.pending_count(), 6 + 6 + 8);
+
+        world.generate_chunk(ChunkPos::new(1, 0));
+        assert_eq!(world.pending_count(), 6 + 8);
+        world.generate_chunk(ChunkPos::new(-1, 0));
+        assert_eq!(world.pending_count(), 0);
+        // The post went through the stone of the west chunk.
+        assert!((0..8).all(|y| world.block(IVec3::new(-1, y, 3)) == Some(cobblestone)));
+
+        world.generate_around(ChunkPos::new(0, 0), 2);
+        assert_eq!(world.pending_count(), 0);
+    }
+
+    #[test]
+    fn claims_are_dropped_once_neighbours_exist() {
+        let mut world = bar_world(1);
+        world.generate_around(ChunkPos::new(0, 0), 2);
+        let mut claimed: Vec<_> = world.claims.keys().copied().collect();
+        claimed.sort();
+        // Only the outermost ring still has neighbours to come.
+        assert!(!claimed.is_empty());
+        assert!(claimed.iter().all(|pos| pos.x.abs().max(pos.z.abs()) == 2), "{:?}", claimed);
+
+        world.generate_around(ChunkPos::new(0, 0), 3);
+        assert!(world.claims.keys().all(|pos| pos.x.abs().max(pos.z.abs()) == 3));
+        // Edits in surrounded chunks are not recorded either.
+        world.set_block(IVec3::new(0, 10, 0), world.registry().expect_id("stone"));
+        assert!(!world.claims.contains_key(&ChunkPos::new(0, 0)));
+    }
+
+    #[test]
+    fn features_leave_player_edits_alone() {
+        let mut world = bar_world(100);
+        let stone = world.registry().expect_id("stone");
+        let air = BlockId::AIR;
+        let cobblestone = world.registry().expect_id("cobblestone");
+        world.generate_chunk(ChunkPos::new(1, 0));
+        world.generate_chunk(ChunkPos::new(-1, 0));
+
+        // Build where the bar of chunk (0, 0) will pass and dig where its post will stand.
+        let y = 4 + (hash_coords(1, 0, 0, 0) & 1) as i32;
+        world.set_block(IVec3::new(17, y, 7), stone);
+        world.set_block(IVec3::new(-1, 2, 3), air);
+        world.generate_chunk(ChunkPos::new(0, 0));
+
+        assert_eq!(world.block(IVec3::new(17, y, 7)), Some(stone));
+        assert_ne!(world.block(IVec3::new(18, y, 7)), Some(air));
+        assert_eq!(world.block(IVec3::new(-1, 2, 3)), Some(air));
+        assert_eq!(world.block(IVec3::new(-1, 1, 3)), Some(cobblestone));
+    }
+}
@@ -0,0 +1,15 @@
+//! Voxel world storage.
+
+mod biome;
+mod chunk;
+mod light;
+mod lighting;
+mod map;
+mod palette;
+
+pub use self::biome::{Biome, BiomeDef, Tint};
+pub use self::chunk::{Chunk, ChunkPos, CHUNK_HEIGHT, CHUNK_SIZE, SECTION_COUNT};
+pub use self::light::{LightChannel, LightSection, MAX_LIGHT};
+pub use self::lighting::light_chunk;
+pub use self::map::World;
+pub use self::palette::PalettedContainer;
@@ -0,0 +1,254 @@
+use std::mem::size_of;
+
+use crate::block::BlockId;
+
+/// Fixed-length array of block IDs stored as indices into a local palette.
+///
+/// Indices are bit-packed into `u64` words using the fewest bits that can
+/// address the palette (entries never straddle two words). A container that
+/// holds a single value uses no index storage at all. Per-entry reference
+/// counts let unused palette slots be recycled without rescanning the data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PalettedContainer {
+    len: usize,
+    palette: Vec<BlockId>,
+    /// How many entries currently use each palette slot.
+    counts: Vec<u32>,
+    bits: u32,
+    data: Vec<u64>,
+}
+
+impl PalettedContainer {
+    pub fn new(len: usize, value: BlockId) -> Self {
+        PalettedContainer {
+            len,
+            palette: vec![value],
+            counts: vec![len as u32],
+            bits: 0,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bits used per entry; 0 when the whole container holds one value.
+    pub fn bits_per_entry(&self) -> u32 {
+        self.bits
+    }
+
+    /// Distinct values currently stored.
+    pub fn distinct_values(&self) -> usize {
+        self.counts.iter().filter(|&&c| c > 0).count()
+    }
+
+    /// The value every entry holds, if they are all the same.
+    pub fn uniform_value(&self) -> Option<BlockId> {
+        let mut used = self.palette.iter().zip(&self.counts).filter(|(_, &c)| c > 0);
+        match (used.next(), used.next()) {
+            (Some((&value, _)), None) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// How many entries hold `value`.
+    pub fn count(&self, value: BlockId) -> usize {
+        self.palette
+            .iter()
+            .position(|&v| v == value)
+            .map_or(0, |slot| self.counts[slot] as usize)
+    }
+
+    pub fn get(&self, index: usize) -> BlockId {
+        assert!(index < self.len, "index {} out of range", index);
+        self.palette[self.slot_at(index)]
+    }
+
+    /// Stores `value` at `index` and returns the previous value.
+    pub fn set(&mut self, index: usize, value: BlockId) -> BlockId {
+        assert!(index < self.len, "index {} out of range", index);
+        let old_slot = self.slot_at(index);
+        let old = self.palette[old_slot];
+        if old == value {
+            return old;
+        }
+
+        let slot = self.slot_for(value);
+        self.counts[old_slot] -= 1;
+        self.counts[slot] += 1;
+        self.write_slot(index, slot);
+        old
+    }
+
+    /// Sets every entry to `value`, releasing all index storage.
+    pub fn fill(&mut self, value: BlockId) {
+        *self = PalettedContainer::new(self.len, value);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = BlockId> + '_ {
+        (0..self.len).map(move |i| self.palette[self.slot_at(i)])
+    }
+
+    /// Approximate heap and inline bytes used by this container.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + self.palette.capacity() * size_of::<BlockId>()
+            + self.counts.capacity() * size_of::<u32>()
+            + self.data.capacity() * size_of::<u64>()
+    }
+
+    fn entries_per_word(bits: u32) -> usize {
+        64 / bits as usize
+    }
+
+    fn slot_at(&self, index: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let per_word = Self::entries_per_word(self.bits);
+        let word = self.data[index / per_word];
+        let shift = (index % per_word) as u32 * self.bits;
+        ((word >> shift) & ((1 << self.bits) - 1)) as usize
+    }
+
+    fn write_slot(&mut self, index: usize, slot: usize) {
+        let per_word = Self::entries_per_word(self.bits);
+        let shift = (index % per_word) as u32 * self.bits;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let word = &mut self.data[index / per_word];
+        *word = (*word & !mask) | ((slot as u64) << shift);
+    }
+
+    /// Palette slot for `value`, reusing a free slot or growing the palette if needed.
+    fn slot_for(&mut self, value: BlockId) -> usize {
+        if let Some(slot) = self.palette.iter().position(|&v| v == value) {
+            return slot;
+        }
+        if let Some(slot) = self.counts.iter().position(|&c| c == 0) {
+            self.palette[slot] = value;
+            return slot;
+        }
+        self.palette.push(value);
+        self.counts.push(0);
+        let needed = usize::BITS - (self.palette.len() - 1).leading_zeros();
+        if needed > self.bits {
+            self.repack(needed);
+        }
+        self.palette.len() - 1
+    }
+
+    fn repack(&mut self, bits: u32) {
+        let slots: Vec<usize> = (0..self.len).map(|i| self.slot_at(i)).collect();
+        self.bits = bits;
+        self.data = vec![0; self.len.div_ceil(Self::entries_per_word(bits))];
+        for (index, slot) in slots.into_iter().enumerate() {
+            self.write_slot(index, slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEN: usize = 4096;
+
+    /// Deterministic scatter of indices, so writes do not just run in order.
+    fn scrambled(i: usize) -> usize {
+        (i * 2671 + 17) % LEN
+    }
+
+    #[test]
+    fn uniform_container_has_no_index_storage() {
+        let container = PalettedContainer::new(LEN, BlockId(3));
+        assert_eq!(container.bits_per_entry(), 0);
+        assert_eq!(container.uniform_value(), Some(BlockId(3)));
+        assert_eq!(container.count(BlockId(3)), LEN);
+        assert!(container.iter().all(|block| block == BlockId(3)));
+    }
+
+    #[test]
+    fn round_trips_through_every_bit_width() {
+        let mut container = PalettedContainer::new(LEN, BlockId(0));
+        let mut expected = vec![BlockId(0); LEN];
+        // 2 values need 1 bit, 3..=4 need 2, ... 257 need 9.
+        for distinct in 2..=257u16 {
+            for n in 0..16 {
+                let index = scrambled(distinct as usize * 16 + n);
+                let value = BlockId(distinct - 1 + (n as u16 % 2) * 1000);
+                assert_eq!(container.set(index, value), expected[index]);
+                expected[index] = value;
+            }
+            let wanted_bits = usize::BITS - (container.palette.len() - 1).leading_zeros();
+            assert_eq!(container.bits_per_entry(), wanted_bits);
+            if distinct.is_power_of_two() || distinct == 257 {
+                assert!(container.iter().eq(expected.iter().copied()), "after {} values", distinct);
+            }
+        }
+        assert_eq!(container.bits_per_entry(), 10);
+        for (index, &value) in expected.iter().enumerate() {
+            assert_eq!(container.get(index), value, "index {}", index);
+        }
+    }
+
+    #[test]
+    fn entries_never_straddle_words() {
+        // 3 bits fit 21 entries per word; index 21 starts the next word.
+        let mut container = PalettedContainer::new(LEN, BlockId(0));
+        for (i, value) in (1..=5).enumerate() {
+            container.set(20 + i, BlockId(value));
+        }
+        assert_eq!(container.bits_per_entry(), 3);
+        assert_eq!(container.data.len(), LEN.div_ceil(21));
+        assert_eq!((19..26).map(|i| container.get(i).0).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5, 0]);
+    }
+
+    #[test]
+    fn unused_slots_are_recycled() {
+        let mut container = PalettedContainer::new(LEN, BlockId(0));
+        container.set(0, BlockId(1));
+        container.set(1, BlockId(2));
+        assert_eq!(container.distinct_values(), 3);
+        container.set(0, BlockId(0));
+        assert_eq!(container.count(BlockId(1)), 0);
+        assert_eq!(container.distinct_values(), 2);
+
+        // The freed slot takes the new value instead of growing the palette.
+        container.set(5, BlockId(9));
+        assert_eq!(container.palette.len(), 3);
+        assert_eq!(container.bits_per_entry(), 2);
+        assert_eq!((container.get(1), container.get(5)), (BlockId(2), BlockId(9)));
+    }
+
+    #[test]
+    fn uniform_value_after_overwriting_everything() {
+        let mut container = PalettedContainer::new(LEN, BlockId(0));
+        container.set(7, BlockId(4));
+        assert_eq!(container.uniform_value(), None);
+        for i in 0..LEN {
+            container.set(i, BlockId(4));
+        }
+        assert_eq!(container.uniform_value(), Some(BlockId(4)));
+    }
+
+    #[test]
+    fn fill_releases_storage() {
+        let mut container = PalettedContainer::new(LEN, BlockId(0));
+        let empty = container.memory_usage();
+        for i in 0..LEN {
+            container.set(i, BlockId(i as u16 % 40));
+        }
+        assert_eq!(container.bits_per_entry(), 6);
+        assert!(container.memory_usage() >= empty + LEN * 6 / 8);
+
+        container.fill(BlockId(2));
+        assert_eq!(container.bits_per_entry(), 0);
+        assert_eq!(container.memory_usage(), empty);
+        assert_eq!(container.get(100), BlockId(2));
+    }
+}
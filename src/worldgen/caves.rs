@@ -0,0 +1,166 @@
+//! Cave carving: hollows out the underground after the terrain is laid down.
+//!
+//! Two kinds of caves are combined. Chambers ("cheese" caves) are carved
+//! wherever a squashed 3D fractal noise rises above a threshold, giving wide,
+//! flat rooms. Tunnels ("worm" or spaghetti caves) follow the lines where two
+//! independent 3D noises are both close to zero. Both are pure functions of
+//! world coordinates, so caves line up across chunk borders whatever order
+//! chunks are generated in.
+
+use crate::block::BlockId;
+use crate::world::{Chunk, CHUNK_SIZE};
+
+use super::noise::{derive_seed, Fractal};
+
+#[derive(Clone, Debug)]
+pub struct CaveSettings {
+    /// Chambers are carved where their noise exceeds this; higher means fewer.
+    pub chamber_threshold: f64,
+    /// Frequency of the chamber noise, in cycles per block.
+    pub chamber_frequency: f64,
+    /// Vertical squash of chambers; above 1 makes them flatter.
+    pub chamber_flatness: f64,
+    /// Tunnels are carved where both tunnel noises lie within this of zero.
+    pub tunnel_width: f64,
+    /// Frequency of the tunnel noises, in cycles per block.
+    pub tunnel_frequency: f64,
+    /// Solid layers kept below the surface of columns at or below sea level,
+    /// so caves never open into the sea.
+    pub sea_floor_thickness: i32,
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        CaveSettings {
+            chamber_threshold: 0.26,
+            chamber_frequency: 1.0 / 40.0,
+            chamber_flatness: 2.0,
+            tunnel_width: 0.05,
+            tunnel_frequency: 1.0 / 56.0,
+            sea_floor_thickness: 6,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CaveCarver {
+    settings: CaveSettings,
+    chambers: Fractal,
+    tunnels: [Fractal; 2],
+}
+
+impl CaveCarver {
+    pub fn new(seed: u64, settings: CaveSettings) -> Self {
+        CaveCarver {
+            chambers: Fractal::new(derive_seed(seed, "cave chambers"), 3, settings.chamber_frequency),
+            tunnels: [
+                Fractal::new(derive_seed(seed, "cave tunnels a"), 2, settings.tunnel_frequency),
+                Fractal::new(derive_seed(seed, "cave tunnels b"), 2, settings.tunnel_frequency),
+            ],
+            settings,
+        }
+    }
+
+    /// Whether the block at world `(x, y, z)` lies inside a cave.
+    pub fn is_cave(&self, x: i32, y: i32, z: i32) -> bool {
+        let s = &self.settings;
+        let (x, y, z) = (x as f64, y as f64, z as f64);
+        if self.chambers.sample3(x, y * s.chamber_flatness, z) > s.chamber_threshold {
+            return true;
+        }
+        self.tunnels.iter().all(|tunnel| tunnel.sample3(x, y, z).abs() < s.tunnel_width)
+    }
+
+    /// Replaces cave blocks in `chunk` with air. `heights` holds the surface
+    /// height of every column (indexed by `z * CHUNK_SIZE + x`); nothing at
+    /// or below `floor` is carved.
+    pub fn carve(&self, chunk: &mut Chunk, heights: &[i32], floor: i32, sea_level: i32) {
+        let origin = chunk.pos().origin();
+        for z in 0..CHUNK_SIZE as i32 {
+            for x in 0..CHUNK_SIZE as i32 {
+                let height = heights[z as usize * CHUNK_SIZE + x as usize];
+                let top = if height <= sea_level { height - self.settings.sea_floor_thickness } else { height };
+                for y in floor + 1..=top {
+                    if self.is_cave(origin.x + x, y, origin.z + z) {
+                        chunk.set(x as usize, y as usize, z as usize, BlockId::AIR);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ChunkPos, CHUNK_HEIGHT};
+    use crate::worldgen::tests::{generation_orders, hashes_in_order};
+
+    const STONE: BlockId = BlockId(1);
+    const FLOOR: i32 = 4;
+    const SEA_LEVEL: i32 = 62;
+
+    /// Column heights: a sea floor at 50 in the western half, land at 120 in
+    /// the eastern half.
+    fn heights() -> Vec<i32> {
+        (0..CHUNK_SIZE * CHUNK_SIZE).map(|i| if i % CHUNK_SIZE < 8 { 50 } else { 120 }).collect()
+    }
+
+    fn carved(carver: &CaveCarver, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        chunk.fill(STONE);
+        carver.carve(&mut chunk, &heights(), FLOOR, SEA_LEVEL);
+        chunk
+    }
+
+    #[test]
+    fn carving_does_not_depend_on_generation_order() {
+        let [row_major, others @ ..] = generation_orders(2);
+        let carver = CaveCarver::new(8, CaveSettings::default());
+        let expected = hashes_in_order(&row_major, |pos| carved(&carver, pos));
+        for order in others {
+            let carver = CaveCarver::new(8, CaveSettings::default());
+            assert_eq!(hashes_in_order(&order, |pos| carved(&carver, pos)), expected);
+        }
+    }
+
+    #[test]
+    fn carves_exactly_the_caves_between_floor_and_surface() {
+        let settings = CaveSettings::default();
+        let carver = CaveCarver::new(8, settings.clone());
+        let heights = heights();
+        let mut caves = 0;
+        for pos in &generation_orders(1)[0] {
+            let chunk = carved(&carver, *pos);
+            let origin = pos.origin();
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let height = heights[z * CHUNK_SIZE + x];
+                    let top = if height <= SEA_LEVEL { height - settings.sea_floor_thickness } else { height };
+                    for y in 0..CHUNK_HEIGHT as i32 {
+                        let cave = y > FLOOR && y <= top && carver.is_cave(origin.x + x as i32, y, origin.z + z as i32);
+                        assert_eq!(chunk.get(x, y as usize, z).is_air(), cave, "{:?} at {:?}", pos, (x, y, z));
+                        caves += cave as usize;
+                    }
+                }
+            }
+        }
+        assert!(caves > 1000, "only {} cave blocks", caves);
+    }
+
+    #[test]
+    fn tunnels_only_where_both_noises_are_near_zero() {
+        let settings = CaveSettings { chamber_threshold: f64::INFINITY, ..CaveSettings::default() };
+        let carver = CaveCarver::new(3, settings.clone());
+        let mut tunnels = 0;
+        for x in 0..64 {
+            for y in 5..80 {
+                let near_zero =
+                    carver.tunnels.iter().all(|t| t.sample3(x as f64, y as f64, 7.0).abs() < settings.tunnel_width);
+                assert_eq!(carver.is_cave(x, y, 7), near_zero);
+                tunnels += near_zero as usize;
+            }
+        }
+        assert!(tunnels > 0);
+    }
+}
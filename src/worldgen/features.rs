@@ -0,0 +1,296 @@
+//! Decoration features: trees, boulders and small houses placed on top of
+//! the generated terrain.
+//!
+//! Features are chosen per chunk from the chunk's own terrain, using a
+//! random stream derived from the world seed and the chunk position, but may
+//! spill into neighbouring chunks. They are therefore emitted as a list of
+//! `FeatureBlock`s in world coordinates rather than written into the chunk;
+//! `World` applies them, deferring blocks aimed at chunks that do not exist
+//! yet. Every block carries the `rank` of its feature so that overlapping
+//! features resolve the same way whichever chunk is generated first.
+
+use crate::block::{BlockId, BlockRegistry};
+use crate::math::IVec3;
+use crate::world::{Biome, Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
+
+use super::noise::{derive_seed, hash_coords, splitmix64};
+
+/// Which blocks of the undecorated terrain a feature block may overwrite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Replace {
+    /// Only air.
+    Air,
+    /// Air and loose ground: dirt, grass, sand and snow.
+    Ground,
+    /// Anything except bedrock.
+    Any,
+}
+
+/// One block written by a feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeatureBlock {
+    /// World block position.
+    pub pos: IVec3,
+    pub block: BlockId,
+    pub replace: Replace,
+    /// Where features overlap, the block of the highest-ranked feature wins.
+    pub rank: u64,
+}
+
+/// Collects the blocks of one feature.
+struct Builder<'a> {
+    out: &'a mut Vec<FeatureBlock>,
+    rank: u64,
+}
+
+impl Builder<'_> {
+    fn set(&mut self, pos: IVec3, block: BlockId, replace: Replace) {
+        if (0..CHUNK_HEIGHT as i32).contains(&pos.y) {
+            self.out.push(FeatureBlock { pos, block, replace, rank: self.rank });
+        }
+    }
+
+    fn fill(&mut self, min: IVec3, max: IVec3, block: BlockId, replace: Replace) {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    self.set(IVec3::new(x, y, z), block, replace);
+                }
+            }
+        }
+    }
+}
+
+/// Kinds of feature, in increasing priority: a house wins over the trees
+/// and boulders it overlaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Tree,
+    Boulder,
+    House,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Palette {
+    log: BlockId,
+    leaves: BlockId,
+    cobblestone: BlockId,
+    planks: BlockId,
+    glass: BlockId,
+    torch: BlockId,
+    grass: BlockId,
+    dirt: BlockId,
+    sand: BlockId,
+    snow: BlockId,
+    bedrock: BlockId,
+}
+
+/// Places the features of each chunk.
+#[derive(Clone, Debug)]
+pub struct Decorator {
+    seed: u64,
+    blocks: Palette,
+    /// Chance per chunk of a boulder, before biome adjustments.
+    pub boulder_chance: f64,
+    /// Chance per chunk of a house in plains and savanna.
+    pub house_chance: f64,
+}
+
+impl Decorator {
+    /// Panics if the registry lacks one of the blocks features are built from.
+    pub fn new(seed: u64, registry: &BlockRegistry) -> Self {
+        Decorator {
+            seed: derive_seed(seed, "features"),
+            blocks: Palette {
+                log: registry.expect_id("log"),
+                leaves: registry.expect_id("leaves"),
+                cobblestone: registry.expect_id("cobblestone"),
+                planks: registry.expect_id("planks"),
+                glass: registry.expect_id("glass"),
+                torch: registry.expect_id("torch"),
+                grass: registry.expect_id("grass"),
+                dirt: registry.expect_id("dirt"),
+                sand: registry.expect_id("sand"),
+                snow: registry.expect_id("snow"),
+                bedrock: registry.expect_id("bedrock"),
+            },
+            boulder_chance: 0.15,
+            house_chance: 0.03,
+        }
+    }
+
+    /// Whether a feature block with `replace` may overwrite `base`, the block
+    /// the terrain generator put there.
+    pub fn can_replace(&self, replace: Replace, base: BlockId) -> bool {
+        let b = &self.blocks;
+        match replace {
+            Replace::Air => base.is_air(),
+            Replace::Ground => base.is_air() || [b.grass, b.dirt, b.sand, b.snow].contains(&base),
+            Replace::Any => base != b.bedrock,
+        }
+    }
+
+    /// The features rooted in `chunk`, which must be undecorated terrain.
+    pub fn decorate(&self, chunk: &Chunk) -> Vec<FeatureBlock> {
+        let pos = chunk.pos();
+        let origin = pos.origin();
+        let mut rng = hash_coords(self.seed, pos.x, 0, pos.z);
+        let mut out = Vec::new();
+
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let Some(top) = self.ground(chunk, x, z) else { continue };
+                let biome = chunk.biome(x, z);
+                let roll = (splitmix64(&mut rng) >> 11) as f64 / (1u64 << 53) as f64;
+                if roll < biome.def().foliage_density as f64 {
+                    let root = origin + IVec3::new(x as i32, top + 1, z as i32);
+                    let mut builder = self.builder(&mut out, Kind::Tree, root);
+                    match biome {
+                        Biome::Taiga | Biome::Tundra | Biome::Mountains => self.spruce(&mut builder, root),
+                        _ => self.oak(&mut builder, root),
+                    }
+                }
+            }
+        }
+
+        let (x, z) = (splitmix64(&mut rng) as usize % CHUNK_SIZE, splitmix64(&mut rng) as usize % CHUNK_SIZE);
+        let roll = (splitmix64(&mut rng) >> 11) as f64 / (1u64 << 53) as f64;
+        if let Some(top) = self.ground(chunk, x, z) {
+            let biome = chunk.biome(x, z);
+            let boulder_chance = match biome {
+                Biome::Mountains | Biome::Taiga | Biome::Tundra => self.boulder_chance * 3.0,
+                Biome::Desert | Biome::Swamp => 0.0,
+                _ => self.boulder_chance,
+            };
+            let house_chance = match biome {
+                Biome::Plains | Biome::Savanna => self.house_chance,
+                _ => 0.0,
+            };
+            let center = origin + IVec3::new(x as i32, top, z as i32);
+            if roll < house_chance && self.is_flat(chunk, x, z, top) {
+                let mut builder = self.builder(&mut out, Kind::House, center);
+                self.house(&mut builder, center);
+            } else if roll < house_chance + boulder_chance {
+                let radius = 1 + (splitmix64(&mut rng) % 2) as i32;
+                let mut builder = self.builder(&mut out, Kind::Boulder, center);
+                self.boulder(&mut builder, center, radius);
+            }
+        }
+        out
+    }
+
+    fn builder<'a>(&self, out: &'a mut Vec<FeatureBlock>, kind: Kind, root: IVec3) -> Builder<'a> {
+        // Kind in the top bits, then a hash of the position to order features of the same kind.
+        let rank = ((kind as u64) << 62) | (hash_coords(self.seed, root.x, root.y, root.z) >> 2);
+        Builder { out, rank }
+    }
+
+    /// Height of the grass, dirt or snow block on top of column `(x, z)`,
+    /// if the column is dry land something can grow on.
+    fn ground(&self, chunk: &Chunk, x: usize, z: usize) -> Option<i32> {
+        let b = &self.blocks;
+        let y = (0..CHUNK_HEIGHT - 1).rev().find(|&y| !chunk.get(x, y, z).is_air())?;
+        [b.grass, b.dirt, b.snow].contains(&chunk.get(x, y, z)).then_some(y as i32)
+    }
+
+    /// Whether the ground within two blocks of `(x, z)` (clamped to the
+    /// chunk) is level with `top`, give or take one block.
+    fn is_flat(&self, chunk: &Chunk, x: usize, z: usize, top: i32) -> bool {
+        let range = |v: usize| v.saturating_sub(2)..=(v + 2).min(CHUNK_SIZE - 1);
+        range(z).all(|z| range(x).all(|x| self.ground(chunk, x, z).is_some_and(|y| (y - top).abs() <= 1)))
+    }
+
+    fn oak(&self, builder: &mut Builder, root: IVec3) {
+        let b = &self.blocks;
+        let height = 4 + (hash_coords(self.seed, root.x, 1, root.z) % 3) as i32;
+        let top = root.y + height - 1;
+        for layer in 0..4 {
+            let y = top - 2 + layer;
+            let radius: i32 = if layer < 2 { 2 } else { 1 };
+            for dz in -radius..=radius {
+                for dx in -radius..=radius {
+                    // Round off the corners, keeping a few at random.
+                    let corner = dx.abs() == radius && dz.abs() == radius;
+                    if corner && (layer == 3 || hash_coords(self.seed, root.x + dx, y, root.z + dz) & 1 == 0) {
+                        continue;
+                    }
+                    builder.set(IVec3::new(root.x + dx, y, root.z + dz), b.leaves, Replace::Air);
+                }
+            }
+        }
+        builder.fill(root, IVec3::new(root.x, top, root.z), b.log, Replace::Air);
+    }
+
+    fn spruce(&self, builder: &mut Builder, root: IVec3) {
+        let b = &self.blocks;
+        let height = 6 + (hash_coords(self.seed, root.x, 1, root.z) % 4) as i32;
+        let top = root.y + height - 1;
+        builder.set(IVec3::new(root.x, top + 1, root.z), b.leaves, Replace::Air);
+        for (i, y) in (root.y + 2..=top).rev().enumerate() {
+            // Alternating rings that widen towards the bottom.
+            let radius = if i % 2 == 0 { (i as i32 / 3).min(2) } else { 1.min(i as i32) };
+            for dz in -radius..=radius {
+                for dx in -radius..=radius {
+                    if radius > 0 && dx.abs() == radius && dz.abs() == radius {
+                        continue;
+                    }
+                    builder.set(IVec3::new(root.x + dx, y, root.z + dz), b.leaves, Replace::Air);
+                }
+            }
+        }
+        builder.fill(root, IVec3::new(root.x, top, root.z), b.log, Replace::Air);
+    }
+
+    /// A rough ball of cobblestone half sunk into the ground.
+    fn boulder(&self, builder: &mut Builder, center: IVec3, radius: i32) {
+        let limit = radius * radius + radius;
+        for dy in -radius..=radius {
+            for dz in -radius..=radius {
+                for dx in -radius..=radius {
+                    let pos = center + IVec3::new(dx, dy, dz);
+                    let jitter = (hash_coords(self.seed, pos.x, pos.y, pos.z) % 2) as i32;
+                    if dx * dx + dy * dy + dz * dz <= limit - jitter {
+                        builder.set(pos, self.blocks.cobblestone, Replace::Ground);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A 5 x 5 plank hut with a cobblestone floor, glass windows, a doorway
+    /// facing south and a torch inside. `ground` is the block the floor
+    /// replaces.
+    fn house(&self, builder: &mut Builder, ground: IVec3) {
+        let b = &self.blocks;
+        let min = ground - IVec3::new(2, 0, 2);
+        let max = ground + IVec3::new(2, 4, 2);
+        // Clear the inside and the space above, then build floor, walls and roof.
+        builder.fill(min + IVec3::new(0, 1, 0), max + IVec3::new(0, 2, 0), BlockId::AIR, Replace::Any);
+        builder.fill(min - IVec3::new(0, 2, 0), IVec3::new(max.x, min.y, max.z), b.cobblestone, Replace::Any);
+        for y in min.y + 1..=max.y - 1 {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    if x == min.x || x == max.x || z == min.z || z == max.z {
+                        builder.set(IVec3::new(x, y, z), b.planks, Replace::Any);
+                    }
+                }
+            }
+        }
+        builder.fill(IVec3::new(min.x, max.y, min.z), max, b.planks, Replace::Any);
+        let window_y = ground.y + 2;
+        for pos in [
+            IVec3::new(min.x, window_y, ground.z),
+            IVec3::new(max.x, window_y, ground.z),
+            IVec3::new(ground.x, window_y, min.z),
+        ] {
+            builder.set(pos, b.glass, Replace::Any);
+        }
+        builder.fill(
+            IVec3::new(ground.x, ground.y + 1, max.z),
+            IVec3::new(ground.x, ground.y + 2, max.z),
+            BlockId::AIR,
+            Replace::Any,
+        );
+        builder.set(IVec3::new(min.x + 1, ground.y + 1, min.z + 1), b.torch, Replace::Any);
+    }
+}
@@ -0,0 +1,183 @@
+//! Seeded gradient noise.
+//!
+//! Everything here is a pure function of the seed and the input coordinates:
+//! no global state, no platform-dependent randomness, and only basic float
+//! arithmetic, so the same seed yields the same world everywhere.
+
+/// One step of SplitMix64, used to derive permutation tables and sub-seeds.
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Derives an independent seed for a named generator layer, so layers built
+/// from the same world seed do not produce correlated noise.
+pub fn derive_seed(seed: u64, layer: &str) -> u64 {
+    let mut state = seed;
+    for byte in layer.bytes() {
+        state ^= byte as u64;
+        splitmix64(&mut state);
+    }
+    splitmix64(&mut state)
+}
+
+/// Hash of integer block coordinates, for per-block random decisions.
+pub fn hash_coords(seed: u64, x: i32, y: i32, z: i32) -> u64 {
+    let mut state = seed ^ (x as u32 as u64) ^ ((z as u32 as u64) << 32);
+    state = splitmix64(&mut state) ^ y as u32 as u64;
+    splitmix64(&mut state)
+}
+
+/// `hash_coords` mapped to 0.0..1.0.
+pub fn random_unit(seed: u64, x: i32, y: i32, z: i32) -> f64 {
+    (hash_coords(seed, x, y, z) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Classic Perlin gradient noise in 2D and 3D with a seeded permutation.
+#[derive(Clone)]
+pub struct Perlin {
+    perm: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut state = seed;
+        // Fisher-Yates shuffle.
+        for i in (1..table.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        Perlin { perm: std::array::from_fn(|i| table[i & 255]) }
+    }
+
+    /// Noise at `(x, y)`, roughly in -1.0..1.0 and 0.0 at integer points.
+    pub fn sample2(&self, x: f64, y: f64) -> f64 {
+        let (xi, yi) = (x.floor(), y.floor());
+        let (xf, yf) = (x - xi, y - yi);
+        let (xi, yi) = (xi as i64 as usize & 255, yi as i64 as usize & 255);
+        let (u, v) = (fade(xf), fade(yf));
+
+        let p = &self.perm;
+        let a = p[xi] as usize + yi;
+        let b = p[xi + 1] as usize + yi;
+        let x1 = lerp(grad2(p[a], xf, yf), grad2(p[b], xf - 1.0, yf), u);
+        let x2 = lerp(grad2(p[a + 1], xf, yf - 1.0), grad2(p[b + 1], xf - 1.0, yf - 1.0), u);
+        lerp(x1, x2, v)
+    }
+
+    /// Noise at `(x, y, z)`, roughly in -1.0..1.0 and 0.0 at integer points.
+    pub fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (xi, yi, zi) = (x.floor(), y.floor(), z.floor());
+        let (xf, yf, zf) = (x - xi, y - yi, z - zi);
+        let (xi, yi, zi) = (xi as i64 as usize & 255, yi as i64 as usize & 255, zi as i64 as usize & 255);
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+
+        let p = &self.perm;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+
+        let front = lerp(
+            lerp(grad3(p[aa], xf, yf, zf), grad3(p[ba], xf - 1.0, yf, zf), u),
+            lerp(grad3(p[ab], xf, yf - 1.0, zf), grad3(p[bb], xf - 1.0, yf - 1.0, zf), u),
+            v,
+        );
+        let back = lerp(
+            lerp(grad3(p[aa + 1], xf, yf, zf - 1.0), grad3(p[ba + 1], xf - 1.0, yf, zf - 1.0), u),
+            lerp(
+                grad3(p[ab + 1], xf, yf - 1.0, zf - 1.0),
+                grad3(p[bb + 1], xf - 1.0, yf - 1.0, zf - 1.0),
+                u,
+            ),
+            v,
+        );
+        lerp(front, back, w)
+    }
+}
+
+/// Fractal Brownian motion: several octaves of `Perlin` noise, each at a
+/// higher frequency and lower amplitude than the last.
+#[derive(Clone)]
+pub struct Fractal {
+    noise: Perlin,
+    pub octaves: u32,
+    /// Frequency of the first octave, in cycles per block.
+    pub frequency: f64,
+    /// Frequency multiplier between octaves.
+    pub lacunarity: f64,
+    /// Amplitude multiplier between octaves.
+    pub persistence: f64,
+}
+
+impl Fractal {
+    pub fn new(seed: u64, octaves: u32, frequency: f64) -> Self {
+        Fractal { noise: Perlin::new(seed), octaves, frequency, lacunarity: 2.0, persistence: 0.5 }
+    }
+
+    /// Sum of the octaves at `(x, y)`, normalised to roughly -1.0..1.0.
+    pub fn sample2(&self, x: f64, y: f64) -> f64 {
+        self.sum(|noise, frequency| noise.sample2(x * frequency, y * frequency))
+    }
+
+    /// Sum of the octaves at `(x, y, z)`, normalised to roughly -1.0..1.0.
+    pub fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.sum(|noise, frequency| noise.sample3(x * frequency, y * frequency, z * frequency))
+    }
+
+    fn sum(&self, sample: impl Fn(&Perlin, f64) -> f64) -> f64 {
+        let (mut total, mut max) = (0.0, 0.0);
+        let (mut frequency, mut amplitude) = (self.frequency, 1.0);
+        for _ in 0..self.octaves {
+            total += sample(&self.noise, frequency) * amplitude;
+            max += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+        total / max
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+fn grad2(hash: u8, x: f64, y: f64) -> f64 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn grad3(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    match hash & 15 {
+        0 | 12 => x + y,
+        1 | 14 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 13 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
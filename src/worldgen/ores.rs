@@ -0,0 +1,230 @@
+//! Ore placement: scatters veins of ore through the stone.
+//!
+//! Veins are seeded per chunk: a chunk's veins are a pure function of the
+//! world seed, the ore and the chunk position, and start inside that chunk.
+//! A vein is a random walk of at most `CHUNK_SIZE` blocks, so it can only
+//! reach into the eight surrounding chunks; when generating a chunk the veins
+//! of its neighbours are replayed too and the blocks falling inside it kept.
+//! That makes veins continue across chunk borders regardless of the order
+//! chunks are generated in.
+
+use crate::block::{BlockId, BlockRegistry};
+use crate::math::IVec3;
+use crate::world::{Chunk, ChunkPos, CHUNK_SIZE};
+
+use super::noise::{derive_seed, hash_coords, splitmix64};
+
+#[derive(Clone, Debug)]
+pub struct OreSettings {
+    /// Name of the ore block in the registry.
+    pub block: String,
+    /// Blocks per vein, at most `CHUNK_SIZE`.
+    pub vein_size: u32,
+    /// Height range vein centres are placed in, inclusive.
+    pub min_height: i32,
+    pub max_height: i32,
+    /// Average veins per chunk; fractions give a chance of one more.
+    pub veins_per_chunk: f32,
+}
+
+impl OreSettings {
+    pub fn new(block: &str, vein_size: u32, heights: std::ops::RangeInclusive<i32>, veins_per_chunk: f32) -> Self {
+        OreSettings {
+            block: block.to_owned(),
+            vein_size,
+            min_height: *heights.start(),
+            max_height: *heights.end(),
+            veins_per_chunk,
+        }
+    }
+
+    /// Coal, iron, gold and diamond, rarer and deeper in that order.
+    pub fn defaults() -> Vec<OreSettings> {
+        vec![
+            OreSettings::new("coal_ore", 14, 5..=128, 16.0),
+            OreSettings::new("iron_ore", 8, 5..=64, 10.0),
+            OreSettings::new("gold_ore", 7, 5..=32, 2.5),
+            OreSettings::new("diamond_ore", 6, 5..=16, 1.0),
+        ]
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Ore {
+    block: BlockId,
+    vein_size: u32,
+    min_height: i32,
+    max_height: i32,
+    veins_per_chunk: f32,
+    seed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct OrePlacer {
+    ores: Vec<Ore>,
+    /// Ore only ever replaces this block.
+    host: BlockId,
+}
+
+impl OrePlacer {
+    /// Panics if an ore block is not registered.
+    pub fn new(seed: u64, registry: &BlockRegistry, settings: &[OreSettings]) -> Self {
+        let ores = settings
+            .iter()
+            .map(|ore| Ore {
+                block: registry.expect_id(&ore.block),
+                vein_size: ore.vein_size.min(CHUNK_SIZE as u32),
+                min_height: ore.min_height,
+                max_height: ore.max_height.max(ore.min_height),
+                veins_per_chunk: ore.veins_per_chunk,
+                seed: derive_seed(seed, &format!("ore {}", ore.block)),
+            })
+            .collect();
+        OrePlacer { ores, host: registry.expect_id("stone") }
+    }
+
+    /// Places every vein that reaches into `chunk`.
+    pub fn place(&self, chunk: &mut Chunk) {
+        let pos = chunk.pos();
+        for ore in &self.ores {
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    self.place_veins(chunk, ore, pos.offset(dx, dz));
+                }
+            }
+        }
+    }
+
+    /// Replays the veins started in chunk `source`, keeping the blocks that
+    /// fall inside `chunk`.
+    fn place_veins(&self, chunk: &mut Chunk, ore: &Ore, source: ChunkPos) {
+        let mut rng = hash_coords(ore.seed, source.x, 0, source.z);
+        let mut random = |range: u64| splitmix64(&mut rng) % range;
+
+        let whole = ore.veins_per_chunk.floor();
+        let extra = (random(1 << 24) as f32 / (1 << 24) as f32) < ore.veins_per_chunk - whole;
+        let veins = whole as u32 + extra as u32;
+
+        let offset = chunk.pos().origin() - source.origin();
+        let span = (ore.max_height - ore.min_height + 1) as u64;
+        for _ in 0..veins {
+            // Position relative to `source`, then walk one block at a time.
+            let mut block = IVec3::new(
+                random(CHUNK_SIZE as u64) as i32,
+                ore.min_height + random(span) as i32,
+                random(CHUNK_SIZE as u64) as i32,
+            );
+            for _ in 0..ore.vein_size {
+                let local = block - offset;
+                if Chunk::contains(local.x, local.y, local.z)
+                    && chunk.get(local.x as usize, local.y as usize, local.z as usize) == self.host
+                {
+                    chunk.set(local.x as usize, local.y as usize, local.z as usize, ore.block);
+                }
+                let step = if random(2) == 0 { 1 } else { -1 };
+                match random(3) {
+                    0 => block.x += step,
+                    1 => block.y += step,
+                    _ => block.z += step,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::worldgen::tests::{generation_orders, hashes_in_order};
+
+    fn stone_chunk(registry: &BlockRegistry, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        chunk.fill(registry.expect_id("stone"));
+        chunk
+    }
+
+    #[test]
+    fn ores_do_not_depend_on_generation_order() {
+        let registry = BlockRegistry::builtin();
+        let [row_major, others @ ..] = generation_orders(2);
+        let generate = |placer: &OrePlacer, pos| {
+            let mut chunk = stone_chunk(&registry, pos);
+            placer.place(&mut chunk);
+            chunk
+        };
+        let placer = OrePlacer::new(77, &registry, &OreSettings::defaults());
+        let expected = hashes_in_order(&row_major, |pos| generate(&placer, pos));
+        for order in others {
+            let placer = OrePlacer::new(77, &registry, &OreSettings::defaults());
+            assert_eq!(hashes_in_order(&order, |pos| generate(&placer, pos)), expected);
+        }
+    }
+
+    #[test]
+    fn ores_stay_near_their_height_range() {
+        let registry = BlockRegistry::builtin();
+        let settings = OreSettings::defaults();
+        let placer = OrePlacer::new(5, &registry, &settings);
+        let mut found = vec![0; settings.len()];
+        for pos in &generation_orders(2)[0] {
+            let mut chunk = stone_chunk(&registry, *pos);
+            placer.place(&mut chunk);
+            for (block_pos, block) in chunk.iter_non_air() {
+                let Some(i) = settings.iter().position(|ore| registry.expect_id(&ore.block) == block) else {
+                    continue;
+                };
+                // Vein centres lie in the range; the walk can stray by one block per step.
+                let ore = &settings[i];
+                let reach = ore.vein_size as i32 - 1;
+                assert!(
+                    (ore.min_height - reach..=ore.max_height + reach).contains(&block_pos.y),
+                    "{} at y = {}",
+                    ore.block,
+                    block_pos.y
+                );
+                found[i] += 1;
+            }
+        }
+        assert!(found.iter().all(|&n| n > 0), "{:?}", found);
+    }
+
+    #[test]
+    fn a_vein_is_one_connected_walk_of_at_most_vein_size_blocks() {
+        let registry = BlockRegistry::builtin();
+        for seed in 0..20 {
+            let settings = [OreSettings::new("iron_ore", 8, 40..=40, 1.0)];
+            let placer = OrePlacer::new(seed, &registry, &settings);
+            let ore = &placer.ores[0];
+            let source = ChunkPos::new(3, -2);
+
+            // Replay the single vein of `source` into it and all its neighbours.
+            let mut blocks = HashSet::new();
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    let mut chunk = stone_chunk(&registry, source.offset(dx, dz));
+                    placer.place_veins(&mut chunk, ore, source);
+                    let origin = chunk.pos().origin();
+                    blocks.extend(chunk.iter_non_air().filter(|&(_, b)| b == ore.block).map(|(p, _)| origin + p));
+                }
+            }
+            assert!((1..=8).contains(&blocks.len()), "{} blocks", blocks.len());
+            assert!(blocks.iter().all(|p| (33..=47).contains(&p.y)));
+
+            let start = *blocks.iter().next().unwrap();
+            let mut reached = HashSet::from([start]);
+            let mut stack = vec![start];
+            while let Some(p) = stack.pop() {
+                for step in [IVec3::new(1, 0, 0), IVec3::new(0, 1, 0), IVec3::new(0, 0, 1)] {
+                    for next in [p + step, p - step] {
+                        if blocks.contains(&next) && reached.insert(next) {
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+            assert_eq!(reached.len(), blocks.len(), "vein is not connected");
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! The default overworld: fractal-noise heightmap shaped by biomes, layered
+//! with each biome's surface and soil blocks over stone, sand on beaches and
+//! the sea floor, water up to sea level and a rough bedrock floor. Caves are
+//! then carved out of it (`caves`) and ore veins scattered through the
+//! remaining stone (`ores`). Trees, boulders and houses are added by the
+//! decoration stage (`features`).
+//!
+//! Biomes come from two low-frequency climate noises, temperature and
+//! humidity. Every biome sits at a point in that climate space and weighs in
+//! on a column's height by how close the column's climate is to it, so
+//! terrain blends smoothly across biome borders; the closest biome decides
+//! the surface blocks.
+
+use crate::block::{BlockId, BlockRegistry};
+use crate::math::IVec3;
+use crate::world::{Biome, Chunk, ChunkPos, CHUNK_HEIGHT, CHUNK_SIZE};
+
+use super::caves::{CaveCarver, CaveSettings};
+use super::features::{Decorator, FeatureBlock, Replace};
+use super::noise::{derive_seed, random_unit, Fractal};
+use super::ores::{OrePlacer, OreSettings};
+use super::TerrainGenerator;
+
+/// Shape of the generated terrain.
+#[derive(Clone, Debug)]
+pub struct TerrainSettings {
+    /// Water fills every column up to this height.
+    pub sea_level: i32,
+    /// Average surface height.
+    pub base_height: i32,
+    /// Height variation of large, gentle landmasses.
+    pub continent_amplitude: f64,
+    /// Height variation of the hills on top of them.
+    pub hill_amplitude: f64,
+    /// Dirt (or sand) layers between the surface and the stone.
+    pub soil_depth: i32,
+    /// Topmost layer that may still contain bedrock.
+    pub bedrock_depth: i32,
+    /// Surfaces above this height are covered in snow.
+    pub snow_line: i32,
+    /// How far, in climate units, a biome's influence on the terrain height
+    /// reaches; larger values give wider transitions.
+    pub biome_blend: f64,
+    pub caves: CaveSettings,
+    pub ores: Vec<OreSettings>,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        TerrainSettings {
+            sea_level: 62,
+            base_height: 66,
+            continent_amplitude: 28.0,
+            hill_amplitude: 14.0,
+            soil_depth: 3,
+            bedrock_depth: 4,
+            snow_line: 110,
+            biome_blend: 0.2,
+            caves: CaveSettings::default(),
+            ores: OreSettings::defaults(),
+        }
+    }
+}
+
+/// Block IDs used by the generator, resolved once from the registry.
+#[derive(Clone, Copy, Debug)]
+struct Palette {
+    bedrock: BlockId,
+    stone: BlockId,
+    sand: BlockId,
+    snow: BlockId,
+    water: BlockId,
+    /// Surface and soil blocks of each biome, indexed by `Biome`.
+    biomes: [(BlockId, BlockId); Biome::ALL.len()],
+}
+
+/// The default `TerrainGenerator`.
+#[derive(Clone)]
+pub struct OverworldGenerator {
+    seed: u64,
+    settings: TerrainSettings,
+    blocks: Palette,
+    continents: Fractal,
+    hills: Fractal,
+    temperature: Fractal,
+    humidity: Fractal,
+    bedrock_seed: u64,
+    caves: CaveCarver,
+    ores: OrePlacer,
+    decorator: Decorator,
+}
+
+impl OverworldGenerator {
+    /// Panics if the registry lacks one of the blocks the generator places.
+    pub fn new(seed: u64, registry: &BlockRegistry) -> Self {
+        Self::with_settings(seed, registry, TerrainSettings::default())
+    }
+
+    pub fn with_settings(seed: u64, registry: &BlockRegistry, settings: TerrainSettings) -> Self {
+        OverworldGenerator {
+            seed,
+            caves: CaveCarver::new(seed, settings.caves.clone()),
+            ores: OrePlacer::new(seed, registry, &settings.ores),
+            decorator: Decorator::new(seed, registry),
+            settings,
+            blocks: Palette {
+                bedrock: registry.expect_id("bedrock"),
+                stone: registry.expect_id("stone"),
+                sand: registry.expect_id("sand"),
+                snow: registry.expect_id("snow"),
+                water: registry.expect_id("water"),
+                biomes: Biome::ALL.map(|biome| {
+                    let def = biome.def();
+                    (registry.expect_id(def.surface), registry.expect_id(def.soil))
+                }),
+            },
+            continents: Fractal::new(derive_seed(seed, "continents"), 4, 1.0 / 512.0),
+            hills: Fractal::new(derive_seed(seed, "hills"), 5, 1.0 / 96.0),
+            temperature: Fractal::new(derive_seed(seed, "temperature"), 3, 1.0 / 700.0),
+            humidity: Fractal::new(derive_seed(seed, "humidity"), 3, 1.0 / 600.0),
+            bedrock_seed: derive_seed(seed, "bedrock"),
+        }
+    }
+
+    pub fn settings(&self) -> &TerrainSettings {
+        &self.settings
+    }
+
+    /// Temperature and humidity of the column at world `(x, z)`, both in -1.0..=1.0.
+    pub fn climate_at(&self, x: i32, z: i32) -> (f64, f64) {
+        let (x, z) = (x as f64, z as f64);
+        // Fractal noise rarely strays far from zero; stretch it to cover all biomes.
+        let temperature = (self.temperature.sample2(x, z) * 2.5).clamp(-1.0, 1.0);
+        let humidity = (self.humidity.sample2(x, z) * 2.5).clamp(-1.0, 1.0);
+        (temperature, humidity)
+    }
+
+    /// Influence of every biome on the column at world `(x, z)`, indexed by
+    /// `Biome` and summing to 1.
+    pub fn biome_weights(&self, x: i32, z: i32) -> [f64; Biome::ALL.len()] {
+        let (temperature, humidity) = self.climate_at(x, z);
+        let spread = 2.0 * self.settings.biome_blend * self.settings.biome_blend;
+        let mut weights = Biome::ALL.map(|biome| {
+            let def = biome.def();
+            let distance2 = (def.temperature - temperature).powi(2) + (def.humidity - humidity).powi(2);
+            (-distance2 / spread).exp()
+        });
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            weights.iter_mut().for_each(|weight| *weight /= total);
+        } else {
+            // Far from every biome; fall back to the closest one.
+            weights = [0.0; Biome::ALL.len()];
+            weights[self.closest_biome(temperature, humidity) as usize] = 1.0;
+        }
+        weights
+    }
+
+    /// The biome whose climate is closest to the column at world `(x, z)`.
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let (temperature, humidity) = self.climate_at(x, z);
+        self.closest_biome(temperature, humidity)
+    }
+
+    fn closest_biome(&self, temperature: f64, humidity: f64) -> Biome {
+        let distance2 = |biome: &Biome| {
+            let def = biome.def();
+            (def.temperature - temperature).powi(2) + (def.humidity - humidity).powi(2)
+        };
+        Biome::ALL
+            .into_iter()
+            .min_by(|a, b| distance2(a).total_cmp(&distance2(b)))
+            .unwrap_or_default()
+    }
+
+    /// Height of the topmost solid block of the column at world `(x, z)`.
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        let s = &self.settings;
+        let weights = self.biome_weights(x, z);
+        let blend = |f: fn(Biome) -> f64| Biome::ALL.iter().zip(weights).map(|(&b, w)| f(b) * w).sum::<f64>();
+        let height_offset = blend(|biome| biome.def().height_offset);
+        let height_scale = blend(|biome| biome.def().height_scale);
+
+        let (x, z) = (x as f64, z as f64);
+        let continent = self.continents.sample2(x, z);
+        // Hills flatten out towards the sea and grow on high ground.
+        let hilliness = (continent * 1.5 + 0.75).clamp(0.2, 1.5) * height_scale;
+        let hills = self.hills.sample2(x, z) * hilliness;
+        let height =
+            s.base_height as f64 + height_offset + continent * s.continent_amplitude + hills * s.hill_amplitude;
+        (height.round() as i32).clamp(s.bedrock_depth + 1, CHUNK_HEIGHT as i32 - 2)
+    }
+
+    /// Whether `y` (at most `bedrock_depth`) is bedrock: always at the very
+    /// bottom, thinning out towards the top of the layer.
+    fn is_bedrock(&self, x: i32, y: i32, z: i32) -> bool {
+        let chance = 1.0 - y as f64 / (self.settings.bedrock_depth + 1) as f64;
+        y == 0 || random_unit(self.bedrock_seed, x, y, z) < chance
+    }
+
+    /// Lays down the column at local `(x, z)` and returns its surface height.
+    fn generate_column(&self, chunk: &mut Chunk, x: i32, z: i32) -> i32 {
+        let s = &self.settings;
+        let b = &self.blocks;
+        let world = chunk.pos().origin() + IVec3::new(x, 0, z);
+        let height = self.height_at(world.x, world.z);
+        let biome = self.biome_at(world.x, world.z);
+        chunk.set_biome(x as usize, z as usize, biome);
+
+        let (surface, soil) = match b.biomes[biome as usize] {
+            // Columns reaching at most one block above the water are beach or sea floor.
+            _ if height <= s.sea_level + 1 => (b.sand, b.sand),
+            (_, soil) if height > s.snow_line => (b.snow, soil),
+            blocks => blocks,
+        };
+        let soil_bottom = (height - s.soil_depth).max(0);
+
+        if soil_bottom > 0 {
+            chunk.fill_box(IVec3::new(x, 0, z), IVec3::new(x, soil_bottom - 1, z), b.stone);
+        }
+        if soil_bottom < height {
+            chunk.fill_box(IVec3::new(x, soil_bottom, z), IVec3::new(x, height - 1, z), soil);
+        }
+        chunk.set(x as usize, height as usize, z as usize, surface);
+        if height < s.sea_level {
+            chunk.fill_box(IVec3::new(x, height + 1, z), IVec3::new(x, s.sea_level, z), b.water);
+        }
+        for y in 0..=s.bedrock_depth.min(height) {
+            if self.is_bedrock(world.x, y, world.z) {
+                chunk.set(x as usize, y as usize, z as usize, b.bedrock);
+            }
+        }
+        height
+    }
+}
+
+impl TerrainGenerator for OverworldGenerator {
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn generate(&self, pos: ChunkPos) -> Chunk {
+        let s = &self.settings;
+        let mut chunk = Chunk::new(pos);
+        let mut heights = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+        for z in 0..CHUNK_SIZE as i32 {
+            for x in 0..CHUNK_SIZE as i32 {
+                heights.push(self.generate_column(&mut chunk, x, z));
+            }
+        }
+        self.caves.carve(&mut chunk, &heights, s.bedrock_depth, s.sea_level);
+        self.ores.place(&mut chunk);
+        chunk
+    }
+
+    fn decorate(&self, chunk: &Chunk) -> Vec<FeatureBlock> {
+        self.decorator.decorate(chunk)
+    }
+
+    fn can_replace(&self, replace: Replace, base: BlockId) -> bool {
+        self.decorator.can_replace(replace, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITIONS: [(i32, i32); 4] = [(0, 0), (-3, 5), (17, -9), (-40, -40)];
+
+    /// Chunks along a line until enough columns lie below sea level.
+    fn chunks_with_sea(generator: &OverworldGenerator) -> Vec<Chunk> {
+        let sea_level = generator.settings().sea_level;
+        let mut chunks = Vec::new();
+        let mut sea_columns = 0;
+        for i in 0..256 {
+            let pos = ChunkPos::new(i * 3, -i * 2);
+            let origin = pos.origin();
+            let below = (0..CHUNK_SIZE as i32)
+                .flat_map(|x| (0..CHUNK_SIZE as i32).map(move |z| (x, z)))
+                .filter(|&(x, z)| generator.height_at(origin.x + x, origin.z + z) < sea_level)
+                .count();
+            if below > 0 || chunks.len() < 4 {
+                sea_columns += below;
+                chunks.push(generator.generate(pos));
+            }
+            if sea_columns >= 64 {
+                return chunks;
+            }
+        }
+        panic!("no sea found");
+    }
+
+    #[test]
+    fn same_seed_same_chunks() {
+        let registry = BlockRegistry::builtin();
+        let a = OverworldGenerator::new(1234, &registry);
+        let b = OverworldGenerator::new(1234, &registry);
+        for (x, z) in POSITIONS.into_iter().rev() {
+            let pos = ChunkPos::new(x, z);
+            assert_eq!(a.generate(pos).content_hash(), b.generate(pos).content_hash(), "{:?}", pos);
+        }
+    }
+
+    #[test]
+    fn different_seeds_differ() {
+        let registry = BlockRegistry::builtin();
+        let a = OverworldGenerator::new(1234, &registry);
+        let b = OverworldGenerator::new(1235, &registry);
+        for (x, z) in POSITIONS {
+            let pos = ChunkPos::new(x, z);
+            assert_ne!(a.generate(pos).content_hash(), b.generate(pos).content_hash(), "{:?}", pos);
+        }
+    }
+
+    #[test]
+    fn bedrock_floor() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(99, &registry);
+        let bedrock = registry.expect_id("bedrock");
+        let depth = generator.settings().bedrock_depth;
+        for (x, z) in POSITIONS {
+            let chunk = generator.generate(ChunkPos::new(x, z));
+            for (pos, _) in chunk.iter_non_air().filter(|&(_, block)| block == bedrock) {
+                assert!(pos.y <= depth, "bedrock at {:?}", pos);
+            }
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    assert_eq!(chunk.get(x, 0, z), bedrock);
+                    assert!((1..=depth as usize).all(|y| !chunk.get(x, y, z).is_air()), "hole in the floor");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn water_fills_up_to_sea_level() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(5, &registry);
+        let water = registry.expect_id("water");
+        let sea_level = generator.settings().sea_level;
+        for chunk in chunks_with_sea(&generator) {
+            let origin = chunk.pos().origin();
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let height = generator.height_at(origin.x + x as i32, origin.z + z as i32);
+                    let is_water = |y: i32| chunk.get(x, y as usize, z) == water;
+                    if height < sea_level {
+                        assert!((height + 1..=sea_level).all(is_water), "column {:?}", (x, z));
+                        assert!(!is_water(height), "water replaced the sea floor");
+                    } else {
+                        assert!((0..=height).all(|y| !is_water(y)), "water on land at {:?}", (x, z));
+                    }
+                    assert!(chunk.get(x, sea_level as usize + 1, z) != water);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn biome_weights_sum_to_one() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(3, &registry);
+        for (x, z) in POSITIONS.into_iter().chain([(5000, -7000), (-123_456, 98_765)]) {
+            let weights = generator.biome_weights(x, z);
+            assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9, "{:?}", (x, z));
+            assert!(weights.iter().all(|w| (0.0..=1.0).contains(w)));
+        }
+    }
+
+    #[test]
+    fn climate_at_a_biome_picks_that_biome() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(3, &registry);
+        for biome in Biome::ALL {
+            let def = biome.def();
+            assert_eq!(generator.closest_biome(def.temperature, def.humidity), biome);
+        }
+    }
+
+    #[test]
+    fn height_is_smooth_across_biome_borders() {
+        let registry = BlockRegistry::builtin();
+        let generator = OverworldGenerator::new(7, &registry);
+        let mut borders = 0;
+        for z in (-2000..2000).step_by(250) {
+            for x in -2000..2000 {
+                if generator.biome_at(x, z) == generator.biome_at(x + 1, z) {
+                    continue;
+                }
+                borders += 1;
+                let step = generator.height_at(x + 1, z) - generator.height_at(x, z);
+                assert!(step.abs() <= 2, "{} block step at the border at {:?}", step, (x, z));
+            }
+        }
+        assert!(borders >= 20, "only {} borders crossed", borders);
+    }
+}
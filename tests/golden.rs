@@ -0,0 +1,757 @@
+//! Golden-image regression tests.
+//!
+//! Each `Scene` is rendered off-screen with the `SoftwareRenderer` at a fixed
+//! resolution and compared against a checked-in PNG under `goldens/`. When a
+//! comparison fails, the actual frame and a diff image are written out so the
+//! change can be inspected. Scenes can also probe the depth buffer of the
+//! pass at given pixels.
+//!
+//! Run with `BLESS=1 cargo test --test golden [scene]` to overwrite the
+//! goldens with the current output once a change is intended.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use metalcraft::block::{BlockId, BlockRegistry};
+use metalcraft::camera::Camera;
+use metalcraft::math::{IVec3, Vec3};
+use metalcraft::mesh::{Mesh, Vertex};
+use metalcraft::mesher::{ChunkNeighbors, Mesher, MeshingMode};
+use metalcraft::world::{light_chunk, Biome, BiomeDef, Chunk, ChunkPos, World};
+use metalcraft::renderer::{DepthBuffer, Framebuffer, Renderer, SoftwareRenderer, Uniforms};
+use metalcraft::sky::{Sky, WorldClock, MIDNIGHT, NOON};
+use metalcraft::worldgen::{OverworldGenerator, TerrainGenerator};
+
+/// A named, deterministic frame.
+pub struct Scene {
+    pub name: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub draw: fn(&mut dyn Renderer),
+    /// Expected depth-buffer values after the frame.
+    pub depth_probes: &'static [DepthProbe],
+}
+
+/// Expected depth at one pixel, compared within `DEPTH_TOLERANCE`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthProbe {
+    pub x: u32,
+    pub y: u32,
+    pub depth: f32,
+}
+
+pub const DEPTH_TOLERANCE: f32 = 1e-4;
+
+#[test]
+fn triangle() {
+    check(&Scene { name: "triangle", width: 256, height: 192, draw: draw_triangle, depth_probes: &[] });
+}
+
+#[test]
+fn triangle_perspective() {
+    check(&Scene {
+        name: "triangle_perspective",
+        width: 256,
+        height: 192,
+        draw: draw_triangle_perspective,
+        depth_probes: &[],
+    });
+}
+
+#[test]
+fn depth_overlap() {
+    check(&Scene {
+        name: "depth_overlap",
+        width: 128,
+        height: 128,
+        draw: draw_depth_overlap,
+        depth_probes: &[
+            // Both triangles cover the center; the nearer one must win.
+            DepthProbe { x: 64, y: 64, depth: 0.25 },
+            // Only the far triangle covers the lower left.
+            DepthProbe { x: 20, y: 110, depth: 0.75 },
+            // Nothing covers the top corners.
+            DepthProbe { x: 2, y: 2, depth: 1.0 },
+        ],
+    });
+}
+
+#[test]
+fn chunk_mesh() {
+    check(&Scene { name: "chunk_mesh", width: 256, height: 192, draw: draw_chunk_mesh, depth_probes: &[] });
+}
+
+#[test]
+fn chunk_mesh_greedy() {
+    check(&Scene {
+        name: "chunk_mesh_greedy",
+        width: 256,
+        height: 192,
+        draw: draw_chunk_mesh_greedy,
+        depth_probes: &[],
+    });
+}
+
+#[test]
+fn ambient_occlusion() {
+    check(&Scene {
+        name: "ambient_occlusion",
+        width: 256,
+        height: 192,
+        draw: draw_ambient_occlusion,
+        depth_probes: &[],
+    });
+}
+
+#[test]
+fn terrain() {
+    check(&Scene { name: "terrain", width: 256, height: 192, draw: draw_terrain, depth_probes: &[] });
+}
+
+#[test]
+fn caves() {
+    check(&Scene { name: "caves", width: 256, height: 192, draw: draw_caves, depth_probes: &[] });
+}
+
+#[test]
+fn features() {
+    check(&Scene { name: "features", width: 256, height: 192, draw: draw_features, depth_probes: &[] });
+}
+
+#[test]
+fn lighting() {
+    check(&Scene { name: "lighting", width: 256, height: 192, draw: draw_lighting, depth_probes: &[] });
+}
+
+#[test]
+fn lighting_night() {
+    check(&Scene { name: "lighting_night", width: 256, height: 192, draw: draw_lighting_night, depth_probes: &[] });
+}
+
+#[test]
+fn day_cycle() {
+    check(&Scene { name: "day_cycle", width: 256, height: 192, draw: draw_day_cycle, depth_probes: &[] });
+}
+
+#[test]
+fn biome_map() {
+    check(&Scene { name: "biome_map", width: 256, height: 192, draw: draw_biome_map, depth_probes: &[] });
+}
+
+fn draw_triangle(renderer: &mut dyn Renderer) {
+    let triangle = renderer.upload_mesh(&Mesh::triangle());
+    renderer.begin_frame([0.0, 0.0, 0.0, 1.0], &Uniforms::IDENTITY);
+    renderer.draw(triangle);
+    renderer.present();
+}
+
+/// The triangle seen through a perspective camera, off to the side and above.
+fn draw_triangle_perspective(renderer: &mut dyn Renderer) {
+    let mut camera = Camera::new(Vec3::new(0.6, 0.4, 1.2));
+    camera.set_rotation(-0.45, -0.3);
+    camera.set_viewport(256, 192);
+
+    let triangle = renderer.upload_mesh(&Mesh::triangle());
+    renderer.begin_frame([0.0, 0.0, 0.0, 1.0], &Uniforms::from_camera(&camera));
+    renderer.draw(triangle);
+    renderer.present();
+}
+
+/// Two overlapping triangles at depth 0.25 (blue) and 0.75 (orange). The near
+/// one is submitted first, so it only stays on top if depth testing works.
+fn draw_depth_overlap(renderer: &mut dyn Renderer) {
+    let blue = [0.2, 0.4, 1.0, 1.0];
+    let orange = [1.0, 0.6, 0.1, 1.0];
+    let mesh = Mesh::new(
+        vec![
+            Vertex::new([-0.5, 0.6, 0.25, 1.0], blue),
+            Vertex::new([-0.2, -0.4, 0.25, 1.0], blue),
+            Vertex::new([0.7, 0.3, 0.25, 1.0], blue),
+            Vertex::new([0.0, 0.9, 0.75, 1.0], orange),
+            Vertex::new([-0.9, -0.9, 0.75, 1.0], orange),
+            Vertex::new([0.9, -0.9, 0.75, 1.0], orange),
+        ],
+        vec![0, 1, 2, 3, 4, 5],
+    );
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.0, 0.0, 0.0, 1.0], &Uniforms::IDENTITY);
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// A few hand-placed blocks meshed from a chunk: a grass-topped platform, a
+/// tree trunk with leaves and a glass block.
+fn draw_chunk_mesh(renderer: &mut dyn Renderer) {
+    draw_chunk_scene(renderer, MeshingMode::Naive);
+}
+
+/// The `chunk_mesh` scene with merged faces; should look the same.
+fn draw_chunk_mesh_greedy(renderer: &mut dyn Renderer) {
+    draw_chunk_scene(renderer, MeshingMode::Greedy);
+}
+
+fn draw_chunk_scene(renderer: &mut dyn Renderer, mode: MeshingMode) {
+    let registry = BlockRegistry::builtin();
+    let block = |name| registry.expect_id(name);
+
+    let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+    chunk.fill_box(IVec3::new(2, 0, 2), IVec3::new(9, 1, 9), block("dirt"));
+    chunk.fill_box(IVec3::new(2, 2, 2), IVec3::new(9, 2, 9), block("grass"));
+    chunk.fill_box(IVec3::new(4, 3, 4), IVec3::new(4, 6, 4), block("log"));
+    chunk.fill_box(IVec3::new(3, 6, 3), IVec3::new(5, 7, 5), block("leaves"));
+    chunk.set(4, 6, 4, block("log"));
+    chunk.set(7, 3, 7, block("glass"));
+    chunk.set(8, 3, 4, block("stone"));
+    light_chunk(&mut chunk, &registry);
+
+    let mut camera = Camera::new(Vec3::new(15.0, 11.0, 16.0));
+    camera.set_rotation(-0.75, -0.45);
+    camera.set_viewport(256, 192);
+
+    let mesh = Mesher::new(mode).mesh(&chunk, &ChunkNeighbors::default(), &registry);
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.5, 0.7, 1.0, 1.0], &Uniforms::from_camera(&camera));
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// Hand-built ambient occlusion cases on a stone floor, seen from above: a
+/// lone block (sides only), an L-shaped inner corner (level 0), two blocks
+/// touching diagonally (corner only) and a two-step staircase.
+fn draw_ambient_occlusion(renderer: &mut dyn Renderer) {
+    let registry = BlockRegistry::builtin();
+    let stone = registry.expect_id("stone");
+    let planks = registry.expect_id("planks");
+
+    let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+    chunk.fill_box(IVec3::new(0, 0, 0), IVec3::new(11, 0, 11), stone);
+    chunk.set(2, 1, 2, planks);
+    chunk.fill_box(IVec3::new(6, 1, 2), IVec3::new(8, 1, 2), planks);
+    chunk.fill_box(IVec3::new(6, 1, 3), IVec3::new(6, 1, 4), planks);
+    chunk.set(2, 1, 7, planks);
+    chunk.set(3, 1, 8, planks);
+    chunk.fill_box(IVec3::new(7, 1, 7), IVec3::new(9, 1, 9), planks);
+    chunk.fill_box(IVec3::new(8, 2, 8), IVec3::new(9, 2, 9), planks);
+    light_chunk(&mut chunk, &registry);
+
+    let mut camera = Camera::new(Vec3::new(6.0, 9.0, 13.5));
+    camera.set_rotation(0.0, -0.95);
+    camera.set_viewport(256, 192);
+
+    let mesh = Mesher::default().mesh(&chunk, &ChunkNeighbors::default(), &registry);
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.5, 0.7, 1.0, 1.0], &Uniforms::from_camera(&camera));
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// A 5 x 5 chunk patch of generated overworld with a fixed seed.
+fn draw_terrain(renderer: &mut dyn Renderer) {
+    let registry = BlockRegistry::builtin();
+    let generator = OverworldGenerator::new(0x5eed, &registry);
+
+    let chunks: BTreeMap<ChunkPos, Chunk> = (-2..=2)
+        .flat_map(|x| (-2..=2).map(move |z| ChunkPos::new(x, z)))
+        .map(|pos| {
+            let mut chunk = generator.generate(pos);
+            light_chunk(&mut chunk, &registry);
+            (pos, chunk)
+        })
+        .collect();
+    let mesher = Mesher::default();
+    let mut mesh = Mesh::default();
+    for chunk in chunks.values() {
+        let neighbors = ChunkNeighbors::from_fn(chunk.pos(), |pos| chunks.get(&pos));
+        mesh.append(&mesher.mesh(chunk, &neighbors, &registry));
+    }
+
+    let mut camera = Camera::new(Vec3::new(8.0, 120.0, 72.0));
+    camera.set_rotation(0.0, -0.6);
+    camera.set_viewport(256, 192);
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.5, 0.7, 1.0, 1.0], &Uniforms::from_camera(&camera));
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// A 3 x 3 chunk patch of generated overworld cut off at y = 24, showing
+/// caves and ore veins running across chunk borders.
+fn draw_caves(renderer: &mut dyn Renderer) {
+    let registry = BlockRegistry::builtin();
+    let generator = OverworldGenerator::new(0x5eed, &registry);
+
+    let chunks: BTreeMap<ChunkPos, Chunk> = (-1..=1)
+        .flat_map(|x| (-1..=1).map(move |z| ChunkPos::new(x, z)))
+        .map(|pos| {
+            let mut chunk = generator.generate(pos);
+            chunk.fill_box(IVec3::new(0, 25, 0), IVec3::new(15, 255, 15), BlockId::AIR);
+            light_chunk(&mut chunk, &registry);
+            (pos, chunk)
+        })
+        .collect();
+    let mesher = Mesher::default();
+    let mut mesh = Mesh::default();
+    for chunk in chunks.values() {
+        let neighbors = ChunkNeighbors::from_fn(chunk.pos(), |pos| chunks.get(&pos));
+        mesh.append(&mesher.mesh(chunk, &neighbors, &registry));
+    }
+
+    let mut camera = Camera::new(Vec3::new(8.0, 64.0, 48.0));
+    camera.set_rotation(0.0, -0.95);
+    camera.set_viewport(256, 192);
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.5, 0.7, 1.0, 1.0], &Uniforms::from_camera(&camera));
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// A generated house among trees. The chunks are generated outside-in so
+/// most features cross into chunks that do not exist yet and are placed
+/// from the pending list.
+fn draw_features(renderer: &mut dyn Renderer) {
+    let registry = BlockRegistry::builtin();
+    let mut world = World::new(registry.clone(), OverworldGenerator::new(0x5eed, &registry));
+    let center = ChunkPos::new(-2, 2);
+    for radius in (0..=1i32).rev() {
+        for dz in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx.abs() == radius || dz.abs() == radius {
+                    world.generate_chunk(center.offset(dx, dz));
+                }
+            }
+        }
+    }
+
+    let mesher = Mesher::default();
+    let mut mesh = Mesh::default();
+    let mut chunks: Vec<_> = world.chunks().map(Chunk::pos).collect();
+    chunks.sort();
+    for pos in chunks {
+        let chunk = world.chunk(pos).expect("generated above");
+        mesh.append(&mesher.mesh(chunk, &world.neighbors(pos), &registry));
+    }
+
+    let mut camera = Camera::new(Vec3::new(-20.0, 80.0, 56.0));
+    camera.set_rotation(-0.46, -0.52);
+    camera.set_viewport(256, 192);
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.5, 0.7, 1.0, 1.0], &Uniforms::from_camera(&camera));
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// Flat stone ground four blocks deep, plus a fixed set of blocks.
+struct FlatGenerator {
+    stone: BlockId,
+    blocks: Vec<(IVec3, BlockId)>,
+}
+
+impl TerrainGenerator for FlatGenerator {
+    fn seed(&self) -> u64 {
+        0
+    }
+
+    fn generate(&self, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        chunk.fill_box(IVec3::new(0, 0, 0), IVec3::new(15, 3, 15), self.stone);
+        for &(block_pos, block) in &self.blocks {
+            let local = block_pos - pos.origin();
+            if Chunk::contains(local.x, local.y, local.z) {
+                chunk.set(local.x as usize, local.y as usize, local.z as usize, block);
+            }
+        }
+        chunk
+    }
+}
+
+/// A stone shelter open to the south, built block by block: the west room
+/// is lit by a torch, the east room by daylight through a hole in the roof
+/// and from the opening. On the way a second torch is placed and removed,
+/// and a block is put on the floor and taken away again, so the frame
+/// exercises removal and relighting. The incrementally lit world must match
+/// one generated with the final blocks.
+fn draw_lighting(renderer: &mut dyn Renderer) {
+    draw_shelter(renderer, NOON);
+}
+
+/// The `lighting` scene at midnight: moonlight outside, the torch inside.
+fn draw_lighting_night(renderer: &mut dyn Renderer) {
+    draw_shelter(renderer, MIDNIGHT);
+}
+
+fn draw_shelter(renderer: &mut dyn Renderer, time: f32) {
+    let registry = BlockRegistry::builtin();
+    let stone = registry.expect_id("stone");
+    let torch = registry.expect_id("torch");
+    let glowstone = registry.expect_id("glowstone");
+
+    let mut shelter = Vec::new();
+    let mut fill = |min: IVec3, max: IVec3, block| {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    shelter.push((IVec3::new(x, y, z), block));
+                }
+            }
+        }
+    };
+    fill(IVec3::new(2, 4, 2), IVec3::new(12, 7, 2), stone);
+    fill(IVec3::new(2, 4, 3), IVec3::new(2, 7, 8), stone);
+    fill(IVec3::new(12, 4, 3), IVec3::new(12, 7, 8), stone);
+    fill(IVec3::new(7, 4, 3), IVec3::new(7, 7, 6), stone);
+    fill(IVec3::new(2, 8, 2), IVec3::new(12, 8, 8), stone);
+    let roof_hole = IVec3::new(10, 8, 4);
+    let lamp = IVec3::new(4, 4, 3);
+
+    let mut world = World::new(registry.clone(), FlatGenerator { stone, blocks: Vec::new() });
+    world.generate_around(ChunkPos::new(0, 0), 1);
+    for &(pos, block) in &shelter {
+        world.set_block(pos, block);
+    }
+    world.set_block(lamp, torch);
+    world.set_block(IVec3::new(10, 4, 3), torch);
+    world.set_block(IVec3::new(10, 4, 3), BlockId::AIR);
+    world.set_block(roof_hole, BlockId::AIR);
+    world.set_block(IVec3::new(5, 4, 6), glowstone);
+    world.set_block(IVec3::new(5, 4, 6), stone);
+    world.set_block(IVec3::new(5, 4, 6), BlockId::AIR);
+
+    let mut blocks: Vec<_> = shelter.into_iter().filter(|&(pos, _)| pos != roof_hole).collect();
+    blocks.push((lamp, torch));
+    let mut expected = World::new(registry.clone(), FlatGenerator { stone, blocks });
+    expected.generate_around(ChunkPos::new(0, 0), 1);
+    let mut chunks: Vec<_> = world.chunks().map(Chunk::pos).collect();
+    chunks.sort();
+    for &pos in &chunks {
+        let hash = |world: &World| world.chunk(pos).map(Chunk::content_hash);
+        assert_eq!(hash(&world), hash(&expected), "incremental light in chunk {:?} differs", pos);
+    }
+
+    let mesher = Mesher::default();
+    let mut mesh = Mesh::default();
+    for pos in chunks {
+        let chunk = world.chunk(pos).expect("generated above");
+        mesh.append(&mesher.mesh(chunk, &world.neighbors(pos), &registry));
+    }
+
+    let mut camera = Camera::new(Vec3::new(7.5, 7.5, 17.0));
+    camera.set_rotation(0.0, -0.3);
+    camera.set_viewport(256, 192);
+
+    // A paused clock stays at the time it is set to, however it is advanced.
+    let mut clock = WorldClock::default();
+    clock.pause();
+    clock.set_time_of_day(time);
+    clock.advance(std::time::Duration::from_secs(600));
+    let sky = clock.sky();
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame(sky.clear_color(), &Uniforms::from_camera(&camera).with_sky(&sky));
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// One day from midnight (left) to midnight (right): the sky color on top
+/// with the sun's height traced in white and the moon's in grey, and the
+/// sky light multiplier in the bottom band.
+fn draw_day_cycle(renderer: &mut dyn Renderer) {
+    let (width, height) = (256, 192);
+    let band = 144;
+    let to_clip = |column: i32, row: i32| {
+        [column as f32 / width as f32 * 2.0 - 1.0, 1.0 - row as f32 / height as f32 * 2.0]
+    };
+
+    let mut mesh = Mesh::default();
+    let mut rect = |column: i32, rows: std::ops::Range<i32>, color: [f32; 3], depth: f32| {
+        let base = mesh.vertices.len() as u32;
+        for (dx, row) in [(0, rows.end), (1, rows.end), (1, rows.start), (0, rows.start)] {
+            let [x, y] = to_clip(column + dx, row);
+            mesh.vertices.push(Vertex::new([x, y, depth, 1.0], [color[0], color[1], color[2], 1.0]));
+        }
+        mesh.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    };
+    for column in 0..width {
+        let sky = Sky::at((column as f32 + 0.5) / width as f32);
+        rect(column, 0..band, sky.color, 0.5);
+        rect(column, band..height, sky.light, 0.5);
+        for (direction, color) in [(sky.sun_direction, [1.0; 3]), (sky.moon_direction, [0.6; 3])] {
+            let row = (band as f32 / 2.0 - direction.y * (band as f32 / 2.0 - 4.0)).round() as i32;
+            rect(column, row - 1..row + 1, color, 0.25);
+        }
+    }
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.0, 0.0, 0.0, 1.0], &Uniforms::IDENTITY);
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// Top-down map of 1024 x 768 blocks of generated overworld, one pixel per
+/// 4 x 4 blocks: blended grass tint on land, water tint below sea level,
+/// darker where lower.
+fn draw_biome_map(renderer: &mut dyn Renderer) {
+    const CELL: i32 = 4;
+    let (width, height) = (256, 192);
+    let registry = BlockRegistry::builtin();
+    let generator = OverworldGenerator::new(0x5eed, &registry);
+    let sea_level = generator.settings().sea_level;
+
+    let mut mesh = Mesh::default();
+    for row in 0..height {
+        for column in 0..width {
+            let (x, z) = ((column - width / 2) * CELL, (row - height / 2) * CELL);
+            let weights = generator.biome_weights(x, z);
+            let terrain = generator.height_at(x, z);
+            let tint = |color: fn(&BiomeDef) -> [f32; 3]| {
+                Biome::ALL.iter().zip(weights).fold([0.0; 3], |sum, (biome, weight)| {
+                    let color = color(biome.def());
+                    [0, 1, 2].map(|i| sum[i] + color[i] * weight as f32)
+                })
+            };
+            let (color, shade) = if terrain < sea_level {
+                (tint(|def| def.water_tint), 0.7 + 0.3 * (terrain - sea_level + 20).max(0) as f32 / 20.0)
+            } else {
+                (tint(|def| def.grass_tint), 0.7 + 0.3 * (terrain - sea_level).min(40) as f32 / 40.0)
+            };
+            let color = [color[0] * shade, color[1] * shade, color[2] * shade, 1.0];
+
+            let to_clip = |column: i32, row: i32| {
+                [column as f32 / width as f32 * 2.0 - 1.0, 1.0 - row as f32 / height as f32 * 2.0]
+            };
+            let base = mesh.vertices.len() as u32;
+            for (dx, dy) in [(0, 1), (1, 1), (1, 0), (0, 0)] {
+                let [cx, cy] = to_clip(column + dx, row + dy);
+                mesh.vertices.push(Vertex::new([cx, cy, 0.5, 1.0], color));
+            }
+            mesh.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+    }
+
+    let mesh = renderer.upload_mesh(&mesh);
+    renderer.begin_frame([0.0, 0.0, 0.0, 1.0], &Uniforms::IDENTITY);
+    renderer.draw(mesh);
+    renderer.present();
+}
+
+/// Color and depth targets of a rendered scene.
+pub struct RenderedScene {
+    pub color: Framebuffer,
+    pub depth: DepthBuffer,
+}
+
+pub fn render_scene(scene: &Scene) -> RenderedScene {
+    let mut renderer = SoftwareRenderer::new(scene.width, scene.height);
+    (scene.draw)(&mut renderer);
+    RenderedScene {
+        color: renderer.framebuffer().clone(),
+        depth: renderer.depth_buffer().clone(),
+    }
+}
+
+/// Result of comparing two images pixel by pixel.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// Pixels where any channel differs by more than the tolerance.
+    pub mismatched_pixels: usize,
+    /// Largest per-channel difference seen anywhere in the image.
+    pub max_difference: u8,
+    /// Mismatches in red over a dimmed grayscale copy of the actual image.
+    pub diff: Framebuffer,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares two images of equal size with a per-channel `tolerance`.
+pub fn compare(actual: &Framebuffer, expected: &Framebuffer, tolerance: u8) -> Comparison {
+    assert_eq!(
+        (actual.width(), actual.height()),
+        (expected.width(), expected.height()),
+        "compared images must have the same size"
+    );
+
+    let mut diff = Framebuffer::new(actual.width(), actual.height());
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    for y in 0..actual.height() {
+        for x in 0..actual.width() {
+            let a = actual.pixel(x, y);
+            let e = expected.pixel(x, y);
+            let difference = (0..4).map(|c| a[c].abs_diff(e[c])).max().unwrap();
+            max_difference = max_difference.max(difference);
+            if difference > tolerance {
+                mismatched_pixels += 1;
+                diff.set_pixel(x, y, [255, 0, 0, 255]);
+            } else {
+                let luma = ((a[0] as u32 * 3 + a[1] as u32 * 6 + a[2] as u32) / 10 / 3) as u8;
+                diff.set_pixel(x, y, [luma, luma, luma, 255]);
+            }
+        }
+    }
+    Comparison { mismatched_pixels, max_difference, diff }
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Io(PathBuf, io::Error),
+    Decode(PathBuf, png::DecodingError),
+    Encode(PathBuf, png::EncodingError),
+    /// The golden exists but is not an 8-bit RGBA image.
+    UnsupportedFormat(PathBuf),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            GoldenError::Decode(path, e) => write!(f, "{}: {}", path.display(), e),
+            GoldenError::Encode(path, e) => write!(f, "{}: {}", path.display(), e),
+            GoldenError::UnsupportedFormat(path) => {
+                write!(f, "{}: golden images must be 8-bit RGBA PNGs", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+pub fn load_png(path: &Path) -> Result<Framebuffer, GoldenError> {
+    let file = File::open(path).map_err(|e| GoldenError::Io(path.to_owned(), e))?;
+    let decoder = png::Decoder::new(BufReader::new(file));
+    let mut reader = decoder.read_info().map_err(|e| GoldenError::Decode(path.to_owned(), e))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(|e| GoldenError::Decode(path.to_owned(), e))?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(GoldenError::UnsupportedFormat(path.to_owned()));
+    }
+    pixels.truncate(info.buffer_size());
+    Ok(Framebuffer::from_rgba(info.width, info.height, pixels))
+}
+
+pub fn save_png(path: &Path, image: &Framebuffer) -> Result<(), GoldenError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| GoldenError::Io(parent.to_owned(), e))?;
+    }
+    let file = File::create(path).map_err(|e| GoldenError::Io(path.to_owned(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| GoldenError::Encode(path.to_owned(), e))?;
+    writer.write_image_data(image.as_bytes()).map_err(|e| GoldenError::Encode(path.to_owned(), e))?;
+    writer.finish().map_err(|e| GoldenError::Encode(path.to_owned(), e))
+}
+
+/// Where goldens are read from and failures are written to.
+#[derive(Clone, Debug)]
+pub struct Harness {
+    pub golden_dir: PathBuf,
+    pub output_dir: PathBuf,
+    /// Maximum allowed per-channel difference.
+    pub tolerance: u8,
+    /// Overwrite goldens with the current output instead of comparing.
+    pub bless: bool,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        Harness {
+            golden_dir: root.join("goldens"),
+            output_dir: root.join("target").join("golden-diffs"),
+            tolerance: 2,
+            bless: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Passed,
+    Blessed,
+    /// No golden exists yet; run with `bless` to create it.
+    Missing(PathBuf),
+    SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+    /// A depth probe read back a different value.
+    DepthMismatch { probe: DepthProbe, actual: f32 },
+    /// The frame differs; `actual` and `diff` are the written images.
+    Failed { comparison: Comparison, actual: PathBuf, diff: PathBuf },
+}
+
+impl Harness {
+    pub fn golden_path(&self, scene: &Scene) -> PathBuf {
+        self.golden_dir.join(format!("{}.png", scene.name))
+    }
+
+    pub fn check(&self, scene: &Scene) -> Result<Outcome, GoldenError> {
+        let RenderedScene { color: actual, depth } = render_scene(scene);
+        for probe in scene.depth_probes {
+            let value = depth.depth(probe.x, probe.y);
+            if (value - probe.depth).abs() > DEPTH_TOLERANCE {
+                return Ok(Outcome::DepthMismatch { probe: *probe, actual: value });
+            }
+        }
+
+        let golden_path = self.golden_path(scene);
+
+        if self.bless {
+            save_png(&golden_path, &actual)?;
+            return Ok(Outcome::Blessed);
+        }
+        if !golden_path.exists() {
+            return Ok(Outcome::Missing(golden_path));
+        }
+
+        let expected = load_png(&golden_path)?;
+        if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+            return Ok(Outcome::SizeMismatch {
+                expected: (expected.width(), expected.height()),
+                actual: (actual.width(), actual.height()),
+            });
+        }
+
+        let comparison = compare(&actual, &expected, self.tolerance);
+        if comparison.passed() {
+            return Ok(Outcome::Passed);
+        }
+        let actual_path = self.output_dir.join(format!("{}.actual.png", scene.name));
+        let diff_path = self.output_dir.join(format!("{}.diff.png", scene.name));
+        save_png(&actual_path, &actual)?;
+        save_png(&diff_path, &comparison.diff)?;
+        Ok(Outcome::Failed { comparison, actual: actual_path, diff: diff_path })
+    }
+}
+
+/// Renders `scene` and fails the test unless it matches its golden, or
+/// blesses it when `BLESS=1` is set.
+fn check(scene: &Scene) {
+    let harness = Harness { bless: env::var("BLESS").as_deref() == Ok("1"), ..Harness::default() };
+    match harness.check(scene) {
+        Ok(Outcome::Passed | Outcome::Blessed) => {}
+        Ok(Outcome::Missing(path)) => panic!("{} not found, rerun with BLESS=1", path.display()),
+        Ok(Outcome::SizeMismatch { expected, actual }) => {
+            panic!("golden is {:?}, rendered {:?}", expected, actual)
+        }
+        Ok(Outcome::DepthMismatch { probe, actual }) => {
+            panic!("depth at ({}, {}) is {}, expected {}", probe.x, probe.y, actual, probe.depth)
+        }
+        Ok(Outcome::Failed { comparison, actual, diff }) => panic!(
+            "{} pixels differ (max delta {}), see {} and {}",
+            comparison.mismatched_pixels,
+            comparison.max_difference,
+            actual.display(),
+            diff.display()
+        ),
+        Err(e) => panic!("{}", e),
+    }
+}